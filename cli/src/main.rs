@@ -1,9 +1,19 @@
 extern crate core;
 
-use clap::{arg, Parser, ValueEnum};
+use clap::{Parser, ValueEnum};
 use dotenv::dotenv;
+use impl_rust_web3::account::{self, Account, Keystore};
+use impl_rust_web3::batch::BatchMode;
+use impl_rust_web3::nft;
+use impl_rust_web3::signer::{self, LocalSigner, RemoteSigner, Signer};
+use impl_rust_web3::tx::{self, PendingReplacement, Replacement, TxStatus};
+use impl_rust_web3::{Address, MintOutcome, TxOptions, TxOutcome, H256, U256};
+use prelude::config::NetworkConfig;
+use prelude::deployments::{self, Deployment, Deployments};
 use prelude::*;
-use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{env, fs};
 
 #[derive(ValueEnum, Clone, Debug)]
 enum Command {
@@ -12,8 +22,20 @@ enum Command {
     CreateMetadata,
     Mint,
     Transfer,
+    TransferBatch,
+    Balances,
     Info,
     Deploy,
+    AccountNew,
+    AccountImport,
+    AccountList,
+    AccountExportAddress,
+    AccountChangePassword,
+    TxSpeedUp,
+    TxCancel,
+    TxStatus,
+    DeploymentsList,
+    DeploymentsUse,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -32,6 +54,9 @@ struct Args {
     #[arg(long, default_value = "nft")]
     name: String,
 
+    #[arg(long, default_value = "NFT")]
+    symbol: String,
+
     #[arg(long, default_value = "nft market sample")]
     description: String,
 
@@ -44,8 +69,13 @@ struct Args {
     )]
     image_url: String,
 
-    #[arg(long, default_value_t = 10)]
-    amount: u128,
+    /// Tokens to mint with nft1155 (default 10), or to transfer (default 1)
+    #[arg(long, value_parser = uint256)]
+    amount: Option<U256>,
+
+    /// Hex encoded data passed to the nft1155 receiver hook on transfer
+    #[arg(long)]
+    data: Option<String>,
 
     #[arg(long, default_value = "QmPDE4pXnFvNtqJ2889HgEQUEft8KCdyMaKKt5zzw3NuMS")]
     content_hash: String,
@@ -53,6 +83,12 @@ struct Args {
     #[arg(long, default_value = "Polygon")]
     network: String,
 
+    #[arg(long, default_value = config::DEFAULT_PATH)]
+    config: String,
+
+    #[arg(long, default_value = config::DEFAULT_PROFILE)]
+    profile: String,
+
     #[arg(long, default_value = "nft721")]
     #[clap(value_enum)]
     contract: Contract,
@@ -63,8 +99,74 @@ struct Args {
     #[arg(long, default_value = "0x0E91D6613a84d7C8b72a289D8b275AF7717C3d2E")]
     to_address: String,
 
-    #[arg(long, default_value_t = 1)]
-    token_id: u128,
+    /// Decimal or 0x-hex, as are all token ids and amounts
+    #[arg(long, default_value = "1", value_parser = uint256)]
+    token_id: U256,
+
+    #[arg(long, value_delimiter = ',', value_parser = uint256)]
+    token_ids: Vec<U256>,
+
+    #[arg(long, value_delimiter = ',', value_parser = uint256)]
+    amounts: Vec<U256>,
+
+    #[arg(long, value_delimiter = ',')]
+    accounts: Vec<String>,
+
+    #[arg(long)]
+    fee_speed: Option<FeeSpeed>,
+
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    #[arg(long)]
+    nonce: Option<u64>,
+
+    #[arg(long)]
+    confirmations: Option<u64>,
+
+    /// Seconds between receipt checks
+    #[arg(long)]
+    poll_interval: Option<f64>,
+
+    /// Seconds to wait for confirmations
+    #[arg(long)]
+    wait_timeout: Option<u64>,
+
+    #[arg(long, default_value_t = false)]
+    multicall: bool,
+
+    #[arg(long)]
+    keystore: Option<String>,
+
+    #[arg(long)]
+    mnemonic_file: Option<String>,
+
+    #[arg(long, default_value = signer::DEFAULT_HD_PATH)]
+    hd_path: String,
+
+    #[arg(long)]
+    remote_signer: Option<String>,
+
+    #[arg(long, default_value = account::DEFAULT_KEYSTORE_DIR)]
+    keystore_dir: String,
+
+    #[arg(long)]
+    account: Option<String>,
+
+    #[arg(long)]
+    alias: Option<String>,
+
+    #[arg(long)]
+    key_file: Option<String>,
+
+    #[arg(long)]
+    tx_hash: Option<String>,
+
+    #[arg(long, default_value = deployments::DEFAULT_PATH)]
+    deployments: String,
+
+    #[arg(long)]
+    address: Option<String>,
 }
 
 #[tokio::main]
@@ -72,21 +174,85 @@ pub async fn main() {
     dotenv().ok();
 
     if let Err(e) = execute(Args::parse()).await {
-        println!("error: {:?}", e);
+        println!("error: {}", e);
+        if let Some(hint) = e.hint() {
+            println!("hint: {}", hint);
+        }
         return;
     }
 }
 
+/// The selected network, pointed at the active contracts in the deployments registry.
+fn network_config(args: &Args) -> CliResult<NetworkConfig> {
+    let profile = config::load(&args.config, &args.profile)?;
+    let mut network = profile.network(&args.network)?;
+    Deployments::load(&args.deployments)?.apply(&mut network);
+    Ok(network)
+}
+
+fn tx_options(args: &Args) -> CliResult<TxOptions> {
+    let poll_interval = match args.poll_interval {
+        Some(secs) => match Duration::try_from_secs_f64(secs) {
+            Ok(interval) if !interval.is_zero() => Some(interval),
+            _ => {
+                return Err(Error::Internal(format!(
+                "invalid --poll-interval: {:?}, must be a finite number of seconds greater than 0",
+                secs
+            )))
+            }
+        },
+        None => None,
+    };
+    if args.wait_timeout == Some(0) {
+        return Err(Error::Internal(
+            "invalid --wait-timeout: must be greater than 0".to_string(),
+        ));
+    }
+
+    Ok(TxOptions {
+        fee_speed: args.fee_speed,
+        gas_limit: args.gas_limit,
+        nonce: args.nonce,
+        confirmations: args.confirmations,
+        poll_interval,
+        timeout: args.wait_timeout.map(Duration::from_secs),
+    })
+}
+
 async fn execute(args: Args) -> CliResult<()> {
-    let network = Network::from_str(&args.network).unwrap();
+    let options = tx_options(&args)?;
 
     match args.command {
-        Command::Balance => impl_rust_web3::get_balance(network)
-            .await
-            .map_err(Error::from),
-        Command::SendEth => impl_rust_web3::send_eth(network, args.ether, args.to_address)
-            .await
-            .map_err(Error::from),
+        Command::Balance => {
+            let address = signer(&args).await?.address();
+            let network = network_config(&args)?;
+            let balance = impl_rust_web3::get_balance(&network, address).await?;
+            let currency = &network.network().currency;
+            println!(
+                "balance: {} {}",
+                unit::to_native(balance.to_string().as_str(), currency.decimals),
+                currency.symbol
+            );
+            Ok(())
+        }
+        Command::SendEth => {
+            let network = network_config(&args)?;
+            let to = to_address(&args)?;
+            let currency = &network.network().currency;
+            let wei = unit::from_native(&args.ether.to_string(), currency.decimals)
+                .and_then(|wei| impl_rust_web3::parse_u256(&wei))
+                .ok_or_else(|| {
+                    Error::Internal(format!(
+                        "invalid amount: {} {}",
+                        args.ether, currency.symbol
+                    ))
+                })?;
+            println!("send wei: {}", wei);
+            impl_rust_web3::send_eth(&network, signer(&args).await?.as_ref(), to, wei, &options)
+                .await
+                .map(print_outcome)
+                .map_err(Error::from)
+        }
         Command::CreateMetadata => {
             if !args.image_url.is_empty() {
                 ipfs::create_metadata_from_url(args.name, args.description, args.image_url)
@@ -98,89 +264,552 @@ async fn execute(args: Args) -> CliResult<()> {
                     .map_err(Error::from)
             }
         }
-        Command::Mint => match args.contract {
-            Contract::Nft721 => {
-                let cli = impl_rust_web3::nft_721::client::Client::new(network);
-                cli.mint(args.content_hash.clone())
-                    .await
-                    .map_err(Error::from)
-            }
-            Contract::Nft1155 => {
-                let cli = impl_rust_web3::nft_1155::client::Client::new(network);
-                cli.mint(args.content_hash.clone(), args.amount)
-                    .await
-                    .map_err(Error::from)
-            }
-        },
-        Command::Transfer => match args.contract {
-            Contract::Nft721 => {
-                let cli = impl_rust_web3::nft_721::client::Client::new(network);
-                cli.transfer(
-                    impl_rust_web3::parse_address(args.to_address).unwrap(),
-                    args.token_id,
-                )
-                    .await
-                    .map_err(Error::from)
+        Command::Mint => {
+            let schema = schema(&args.contract);
+            let cli = nft::client(schema, network_config(&args)?, signer(&args).await?).await?;
+            // an ERC721 token is unique, so --amount only applies to nft1155
+            let amount = match schema {
+                Schema::ERC721 => U256::one(),
+                Schema::ERC1155 => args.amount.unwrap_or_else(|| U256::from(10)),
+            };
+            cli.mint(args.content_hash.clone(), amount, &options)
+                .await
+                .map(print_mint)
+                .map_err(Error::from)
+        }
+        Command::Transfer => {
+            let schema = schema(&args.contract);
+            let cli = nft::client(schema, network_config(&args)?, signer(&args).await?).await?;
+            let data = match &args.data {
+                Some(data) => impl_rust_web3::parse_bytes(data)
+                    .ok_or_else(|| Error::Internal(format!("invalid hex data: {}", data)))?,
+                None => vec![],
+            };
+            cli.transfer(
+                to_address(&args)?,
+                args.token_id,
+                args.amount.unwrap_or_else(U256::one),
+                data,
+                &options,
+            )
+            .await
+            .map(print_outcome)
+            .map_err(Error::from)
+        }
+        Command::TransferBatch => {
+            let cli = impl_rust_web3::nft_1155::client::Client::new(
+                network_config(&args)?,
+                signer(&args).await?,
+            )
+            .await?;
+            cli.transfer_batch(
+                to_address(&args)?,
+                args.token_ids.clone(),
+                args.amounts.clone(),
+                &options,
+            )
+            .await
+            .map(print_outcome)
+            .map_err(Error::from)
+        }
+        Command::Balances => {
+            let signer = signer(&args).await?;
+            let accounts = balance_accounts(&args, signer.address())?;
+            let cli = impl_rust_web3::nft_1155::client::Client::new(network_config(&args)?, signer)
+                .await?;
+            let balances = cli
+                .balance_of_batch(accounts.clone(), args.token_ids.clone())
+                .await?;
+            for ((account, token_id), balance) in accounts.iter().zip(&args.token_ids).zip(balances)
+            {
+                println!("{:?}  token {}: {}", account, token_id, balance);
             }
-            Contract::Nft1155 => {
-                let cli = impl_rust_web3::nft_1155::client::Client::new(network);
-                cli.transfer(
-                    impl_rust_web3::parse_address(args.to_address).unwrap(),
-                    args.token_id,
-                )
-                    .await
-                    .map_err(Error::from)
+            Ok(())
+        }
+        Command::Info => {
+            let schema = schema(&args.contract);
+            let cli = nft::client(schema, network_config(&args)?, signer(&args).await?).await?;
+            let info = cli.info(batch_mode(&args)).await?;
+            println!("------------------------------------------------------------");
+            println!("{:?} info: {:?}", args.contract, cli.contract_address());
+            println!("name = {}", info.name);
+            println!("latestTokenId = {}", info.latest_token_id);
+            println!("totalSupply = {:?}", info.total_supply);
+            println!("totalOwned = {:?}", info.total_owned);
+            println!("------------------------------------------------------------");
+            Ok(())
+        }
+        Command::Deploy => {
+            let schema = schema(&args.contract);
+            let network = network_config(&args)?;
+            // the contract address is only known from the receipt, which is needed to record it
+            if options.wait_policy(&network).confirmations == 0 {
+                return Err(Error::Internal(
+                    "deploy needs at least 1 confirmation to record the contract".to_string(),
+                ));
             }
-        },
-        Command::Info => match args.contract {
-            Contract::Nft721 => {
-                let cli = impl_rust_web3::nft_721::client::Client::new(network);
-                println!("------------------------------------------------------------");
-                println!("Nft721 info: {}", network.nft_721_address());
-                println!("name = {}", cli.name().await?);
-                println!("latestTokenId = {}", cli.latest_token_id().await?);
-                println!("totalSupply = {:?}", cli.total_supply().await?);
-                println!("totalOwned = {:?}", cli.total_owned().await?);
-                println!("------------------------------------------------------------");
-                Ok(())
+            let signer = signer(&args).await?;
+            let outcome = nft::deploy(
+                schema,
+                &network,
+                signer.as_ref(),
+                args.name.clone(),
+                args.symbol.clone(),
+                &options,
+            )
+            .await?;
+            record_deployment(&args, &network, schema, signer.address(), &outcome)?;
+            print_outcome(outcome);
+            Ok(())
+        }
+        Command::AccountNew => {
+            let keystore = Keystore::open(&args.keystore_dir)?;
+            let password = new_password("WALLET_PASSWORD")?;
+            let account = keystore.create(&password, args.alias.as_deref())?;
+            print_account(&account);
+            Ok(())
+        }
+        Command::AccountImport => {
+            let keystore = Keystore::open(&args.keystore_dir)?;
+            let secret = match &args.key_file {
+                Some(path) => fs::read_to_string(path)
+                    .map_err(|e| Error::Internal(format!("{}: {}", path, e)))?,
+                None => rpassword::prompt_password("private key: ")
+                    .map_err(|e| Error::Internal(format!("failed to read key: {}", e)))?,
+            };
+            let password = new_password("WALLET_PASSWORD")?;
+            let account = keystore.import(&secret, &password, args.alias.as_deref())?;
+            print_account(&account);
+            Ok(())
+        }
+        Command::AccountList => {
+            let keystore = Keystore::open(&args.keystore_dir)?;
+            for account in keystore.accounts()? {
+                print_account(&account);
             }
-            Contract::Nft1155 => {
-                let cli = impl_rust_web3::nft_1155::client::Client::new(network);
-                println!("------------------------------------------------------------");
-                println!("Nft1155 info: {}", network.nft_1155_address());
-                println!("name = {}", cli.name().await?);
-                println!("latestTokenId = {}", cli.latest_token_id().await?);
-                println!("totalSupply = {:?}", cli.total_supply().await?);
-                println!("totalOwned = {:?}", cli.total_owned().await?);
-                println!("------------------------------------------------------------");
-                Ok(())
+            Ok(())
+        }
+        Command::AccountExportAddress => {
+            let account = find_account(&args)?;
+            println!("{:?}", account.address);
+            Ok(())
+        }
+        Command::AccountChangePassword => {
+            let account = find_account(&args)?;
+            let password = password(&format!("current password for {:?}: ", account.address))?;
+            let new_password = new_password("WALLET_NEW_PASSWORD")?;
+            Keystore::open(&args.keystore_dir)?.change_password(
+                &account,
+                &password,
+                &new_password,
+            )?;
+            println!("password changed: {:?}", account.address);
+            Ok(())
+        }
+        Command::TxSpeedUp => {
+            let network = network_config(&args)?;
+            let pending = tx::speed_up(
+                &network,
+                signer(&args).await?.as_ref(),
+                tx_hash(&args)?,
+                &options,
+            )
+            .await?;
+            print_pending_replacement(&pending);
+            pending
+                .wait()
+                .await
+                .map(print_replacement)
+                .map_err(Error::from)
+        }
+        Command::TxCancel => {
+            let network = network_config(&args)?;
+            let pending = tx::cancel(
+                &network,
+                signer(&args).await?.as_ref(),
+                tx_hash(&args)?,
+                &options,
+            )
+            .await?;
+            print_pending_replacement(&pending);
+            pending
+                .wait()
+                .await
+                .map(print_replacement)
+                .map_err(Error::from)
+        }
+        Command::DeploymentsList => {
+            let registry = Deployments::load(&args.deployments)?;
+            for schema in [Schema::ERC721, Schema::ERC1155] {
+                let active = registry.active(&args.network, schema);
+                for deployment in registry.list(&args.network, schema) {
+                    let marker = if Some(deployment) == active { "*" } else { " " };
+                    println!(
+                        "{} {}  {}  block {}  tx {}  by {}  at {}  code {}",
+                        marker,
+                        schema,
+                        deployment.address,
+                        deployment
+                            .block_number
+                            .map(|b| b.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        deployment.tx_hash,
+                        deployment.deployer,
+                        deployment.deployed_at,
+                        deployment.bytecode_hash
+                    );
+                }
             }
+            Ok(())
+        }
+        Command::DeploymentsUse => {
+            let address = args
+                .address
+                .as_deref()
+                .ok_or_else(|| Error::Internal("--address is required".to_string()))?;
+            let mut registry = Deployments::load(&args.deployments)?;
+            registry.activate(&args.network, schema(&args.contract), address)?;
+            registry.save()?;
+            println!("using {} on {}", address, args.network);
+            Ok(())
+        }
+        Command::TxStatus => {
+            let network = network_config(&args)?;
+            let status = tx::status(&network, tx_hash(&args)?).await?;
+            print_status(&network, status);
+            Ok(())
+        }
+    }
+}
+
+/// Picks the signer from the flags, falling back to a raw key in `WALLET_SECRET`, and
+/// checks it against `WALLET_ADDRESS` when that is set.
+async fn signer(args: &Args) -> CliResult<Arc<dyn Signer>> {
+    let signer = open_signer(args).await?;
+    signer::ensure_address(signer.as_ref(), expected_address()?)?;
+    Ok(signer)
+}
+
+async fn open_signer(args: &Args) -> CliResult<Arc<dyn Signer>> {
+    if let Some(url) = &args.remote_signer {
+        let address = expected_address()?;
+        return Ok(Arc::new(RemoteSigner::connect(url, address).await?));
+    }
+    if args.account.is_some() {
+        let account = find_account(args)?;
+        let password = password(&format!("password for {:?}: ", account.address))?;
+        return Ok(Arc::new(account.signer(&password)?));
+    }
+    if let Some(path) = &args.keystore {
+        let password = password(&format!("password for {}: ", path))?;
+        return Ok(Arc::new(LocalSigner::from_keystore(path, &password)?));
+    }
+    if let Some(path) = &args.mnemonic_file {
+        let phrase =
+            fs::read_to_string(path).map_err(|e| Error::Internal(format!("{}: {}", path, e)))?;
+        let passphrase = env::var("WALLET_MNEMONIC_PASSPHRASE").ok();
+        return Ok(Arc::new(LocalSigner::from_mnemonic(
+            &phrase,
+            &args.hd_path,
+            passphrase.as_deref(),
+        )?));
+    }
+
+    let secret = env::var("WALLET_SECRET").map_err(|_| {
+        Error::Internal(
+            "no signer: use --account, --keystore, --mnemonic-file or --remote-signer, or set WALLET_SECRET"
+                .to_string(),
+        )
+    })?;
+    Ok(Arc::new(LocalSigner::from_hex(&secret)?))
+}
+
+/// `WALLET_ADDRESS`, when set, names the account the signer is expected to control.
+fn expected_address() -> CliResult<Option<Address>> {
+    match env::var("WALLET_ADDRESS") {
+        Ok(address) if !address.trim().is_empty() => impl_rust_web3::parse_address(address.clone())
+            .map(Some)
+            .ok_or_else(|| Error::Internal(format!("invalid WALLET_ADDRESS: {}", address))),
+        _ => Ok(None),
+    }
+}
+
+/// Reads a password from `WALLET_PASSWORD`, prompting on the terminal when it is unset.
+fn password(prompt: &str) -> CliResult<String> {
+    match env::var("WALLET_PASSWORD") {
+        Ok(password) => Ok(password),
+        Err(_) => rpassword::prompt_password(prompt)
+            .map_err(|e| Error::Internal(format!("failed to read password: {}", e))),
+    }
+}
+
+/// Reads a new password from `var`, or prompts for it twice.
+fn new_password(var: &str) -> CliResult<String> {
+    if let Ok(password) = env::var(var) {
+        return Ok(password);
+    }
+    let read = |prompt: &str| {
+        rpassword::prompt_password(prompt)
+            .map_err(|e| Error::Internal(format!("failed to read password: {}", e)))
+    };
+    let password = read("new password: ")?;
+    if password != read("repeat password: ")? {
+        return Err(Error::Internal("passwords do not match".to_string()));
+    }
+    Ok(password)
+}
+
+fn find_account(args: &Args) -> CliResult<Account> {
+    let account = args
+        .account
+        .as_deref()
+        .ok_or_else(|| Error::Internal("--account is required".to_string()))?;
+    Ok(Keystore::open(&args.keystore_dir)?.find(account)?)
+}
+
+fn to_address(args: &Args) -> CliResult<Address> {
+    impl_rust_web3::parse_address(args.to_address.clone())
+        .ok_or_else(|| Error::Internal(format!("invalid address: {}", args.to_address)))
+}
+
+/// The `--accounts` to query, one per token id. A single account, or the wallet when
+/// none is given, is used for every token id.
+fn balance_accounts(args: &Args, wallet: Address) -> CliResult<Vec<Address>> {
+    let accounts = args
+        .accounts
+        .iter()
+        .map(|a| {
+            impl_rust_web3::parse_address(a.clone())
+                .ok_or_else(|| Error::Internal(format!("invalid address: {}", a)))
+        })
+        .collect::<CliResult<Vec<_>>>()?;
+
+    Ok(match accounts.as_slice() {
+        [] => vec![wallet; args.token_ids.len()],
+        [account] => vec![*account; args.token_ids.len()],
+        _ => accounts,
+    })
+}
+
+fn schema(contract: &Contract) -> Schema {
+    match contract {
+        Contract::Nft721 => Schema::ERC721,
+        Contract::Nft1155 => Schema::ERC1155,
+    }
+}
+
+/// Adds a successful deploy to the registry, making it the contract later commands use.
+fn record_deployment(
+    args: &Args,
+    network: &NetworkConfig,
+    schema: Schema,
+    deployer: Address,
+    outcome: &TxOutcome,
+) -> CliResult<()> {
+    let address = match outcome.contract_address {
+        Some(address) => address,
+        None => {
+            println!(
+                "not recorded in {}: the receipt has no contract address",
+                args.deployments
+            );
+            return Ok(());
+        }
+    };
+    let mut registry = Deployments::load(&args.deployments)?;
+    registry.record(
+        &network.network().name,
+        schema,
+        Deployment {
+            address: format!("{:?}", address),
+            chain_id: network.chain_id(),
+            tx_hash: format!("{:?}", outcome.transaction_hash),
+            block_number: outcome.block_number.map(|b| b.as_u64()),
+            deployer: format!("{:?}", deployer),
+            bytecode_hash: format!("{:?}", nft::bytecode_hash(schema)),
+            deployed_at: Deployment::now(),
         },
-        Command::Deploy => match args.contract {
-            Contract::Nft721 => {
-                let cli = impl_rust_web3::nft_721::client::Client::new(network);
-                cli.deploy().await.map_err(Error::from)
-            }
-            Contract::Nft1155 => {
-                let cli = impl_rust_web3::nft_1155::client::Client::new(network);
-                cli.deploy().await.map_err(Error::from)
+    );
+    registry.save()?;
+    Ok(())
+}
+
+fn uint256(value: &str) -> Result<U256, String> {
+    impl_rust_web3::parse_u256(value)
+        .ok_or_else(|| format!("expected a decimal or 0x-hex uint256, got {}", value))
+}
+
+fn tx_hash(args: &Args) -> CliResult<H256> {
+    let hash = args
+        .tx_hash
+        .as_deref()
+        .ok_or_else(|| Error::Internal("--tx-hash is required".to_string()))?;
+    impl_rust_web3::parse_hash(hash)
+        .ok_or_else(|| Error::Internal(format!("invalid transaction hash: {}", hash)))
+}
+
+fn print_account(account: &Account) {
+    println!(
+        "{:?}  {}  {}",
+        account.address,
+        account.alias.as_deref().unwrap_or("-"),
+        account.path.display()
+    );
+}
+
+fn batch_mode(args: &Args) -> BatchMode {
+    if args.multicall {
+        BatchMode::multicall()
+    } else {
+        BatchMode::Rpc
+    }
+}
+
+fn print_outcome(outcome: TxOutcome) {
+    if let Some(gas) = outcome.gas {
+        match gas.estimate {
+            Some(estimate) => println!("gas estimate: {}, gas limit: {}", estimate, gas.limit),
+            None => println!("gas limit: {}", gas.limit),
+        }
+    }
+    println!("tx id: {:?}", outcome.transaction_hash);
+    if outcome.is_pending() {
+        println!("status: pending");
+        return;
+    }
+    if let Some(block_number) = outcome.block_number {
+        println!("block: {}", block_number);
+    }
+    println!("gas used: {}", outcome.gas_used.unwrap_or_default());
+    if let Some(price) = outcome.effective_gas_price {
+        println!(
+            "gas price: {} gwei",
+            unit::to_native(price.to_string().as_str(), 9)
+        );
+    }
+    println!("status: {}", outcome.status.unwrap_or_default());
+    if let Some(address) = outcome.contract_address {
+        println!("deployed to: {:?}", address);
+    }
+    for event in &outcome.events {
+        let params: Vec<String> = event
+            .params
+            .iter()
+            .map(|p| format!("{}={}", p.name, p.value))
+            .collect();
+        println!("event: {}({})", event.name, params.join(", "));
+    }
+}
+
+fn print_mint(mint: MintOutcome) {
+    print_outcome(mint.tx);
+    for minted in &mint.minted {
+        println!(
+            "minted: token {} x{} to {:?}",
+            minted.token_id, minted.amount, minted.to
+        );
+    }
+}
+
+fn print_pending_replacement(pending: &PendingReplacement) {
+    println!("original: {:?}", pending.original());
+    match pending.replacement() {
+        Some(hash) => println!("replacement: {:?}", hash),
+        None => println!("replacement: refused, the original is already mined"),
+    }
+}
+
+fn print_replacement(replacement: Replacement) {
+    if replacement.replaced() {
+        println!("mined: replacement");
+    } else {
+        println!("mined: original");
+    }
+    print_outcome(replacement.outcome);
+}
+
+fn print_status(network: &NetworkConfig, status: TxStatus) {
+    let (outcome, confirmations) = match status {
+        TxStatus::Pending => {
+            println!("status: pending");
+            return;
+        }
+        TxStatus::Mined {
+            outcome,
+            confirmations,
+        } => {
+            println!("status: mined");
+            (outcome, confirmations)
+        }
+        TxStatus::Failed {
+            outcome,
+            confirmations,
+            revert,
+        } => {
+            println!("status: failed");
+            match revert {
+                Some(revert) => println!("revert: {}", revert),
+                None => println!("revert: unknown (replaying did not reproduce it)"),
             }
-        },
+            (outcome, confirmations)
+        }
+    };
+    println!("confirmations: {}", confirmations);
+    if let Some(fee) = outcome.fee() {
+        let currency = &network.network().currency;
+        println!(
+            "fee: {} {}",
+            unit::to_native(fee.to_string().as_str(), currency.decimals),
+            currency.symbol
+        );
     }
+    print_outcome(outcome);
 }
 
 pub type CliResult<T> = Result<T, Error>;
 
-#[derive(thiserror::Error, Debug, PartialOrd, PartialEq, Clone)]
+#[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("internal error: {0}")]
     Internal(String),
+    #[error("config error: {0}")]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    Web3(#[from] impl_rust_web3::Error),
 }
 
-impl From<impl_rust_web3::Error> for Error {
-    fn from(e: impl_rust_web3::Error) -> Self {
-        let msg = format!("rust-web3 error: {:?}", e);
-        Self::Internal(msg)
+impl Error {
+    fn hint(&self) -> Option<&'static str> {
+        use impl_rust_web3::{Error as Web3Error, Revert};
+
+        match self {
+            Error::Web3(Web3Error::InsufficientBalance { .. }) => {
+                Some("the wallet holds too few of the token; check with --command balances")
+            }
+            Error::Web3(Web3Error::InsufficientFunds(_)) => {
+                Some("the wallet cannot cover value + gas; fund it or lower --gas-limit")
+            }
+            Error::Web3(Web3Error::NonceConflict(_)) => {
+                Some("another transaction already uses this nonce; wait for it to be mined")
+            }
+            Error::Web3(Web3Error::Timeout(_)) => Some("the node did not answer in time; retry"),
+            Error::Web3(Web3Error::NotConfirmed(_)) => {
+                Some("it may still be mined; follow it with --command tx-status")
+            }
+            Error::Web3(Web3Error::Revert(Revert::NotOwner)) => {
+                Some("only the contract owner can do this; use the deploying wallet")
+            }
+            Error::Web3(Web3Error::AddressMismatch { .. }) => {
+                Some("WALLET_ADDRESS is optional; unset it or select the matching key")
+            }
+            Error::Web3(Web3Error::UnsupportedInterface { .. }) => {
+                Some("check the contract address for --network and --contract")
+            }
+            Error::Config(_) | Error::Web3(Web3Error::Config(_)) => {
+                Some("check --config and --profile")
+            }
+            _ => None,
+        }
     }
 }
 
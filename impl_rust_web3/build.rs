@@ -0,0 +1,452 @@
+//! Generates typed bindings for every `src/<contract>/abi.json` into
+//! `$OUT_DIR/<contract>.rs`, included by the contract's `bindings` module. An ABI change
+//! from `make extract-abi` then shows up as a compile error in the code using it.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    let mut contracts: Vec<_> = fs::read_dir("src")
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("abi.json").is_file())
+        .collect();
+    contracts.sort();
+
+    for dir in contracts {
+        let abi_path = dir.join("abi.json");
+        println!("cargo:rerun-if-changed={}", abi_path.display());
+        let name = dir.file_name().unwrap().to_str().unwrap().to_string();
+        let abi: Vec<Value> = serde_json::from_str(&fs::read_to_string(&abi_path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", abi_path.display(), e));
+
+        let code = generate(&abi_path, &abi);
+        fs::write(Path::new(&out_dir).join(format!("{}.rs", name)), code).unwrap();
+    }
+}
+
+fn generate(path: &Path, abi: &[Value]) -> String {
+    let mut structs = BTreeMap::new();
+    let mut out = format!(
+        "// Generated by build.rs from {}; do not edit.\n\n",
+        path.display()
+    );
+
+    let mut functions = BTreeMap::<&str, Vec<&Value>>::new();
+    for function in items(abi, "function") {
+        functions
+            .entry(function["name"].as_str().unwrap())
+            .or_default()
+            .push(function);
+    }
+    let mut code = String::new();
+    for overloads in functions.values_mut() {
+        overloads.sort_by_key(|f| params(f, "inputs").len());
+        let base = params(overloads[0], "inputs");
+        for (i, function) in overloads.iter().enumerate() {
+            let mut name = pascal_case(function["name"].as_str().unwrap());
+            if i > 0 {
+                let extra: Vec<_> = params(function, "inputs")
+                    .iter()
+                    .filter(|p| !base.iter().any(|b| b["name"] == p["name"]))
+                    .map(|p| pascal_case(p["name"].as_str().unwrap_or_default()))
+                    .collect();
+                name = format!("{}With{}", name, extra.concat());
+                if extra.is_empty() {
+                    name.push_str(&params(function, "inputs").len().to_string());
+                }
+            }
+            function_binding(&mut code, &name, function, &mut structs);
+        }
+    }
+    module(
+        &mut out,
+        "functions",
+        "Calls to the contract functions; overloads beyond the shortest are named after \
+         their extra parameters.",
+        "use crate::bindings::ContractCall;\n\
+         use web3::contract::tokens::{Detokenize, Tokenizable};\n\
+         use web3::ethabi::Token;",
+        &code,
+    );
+
+    let mut code = String::new();
+    for event in items(abi, "event").filter(|e| e["anonymous"] != true) {
+        event_binding(&mut code, event, &mut structs);
+    }
+    module(
+        &mut out,
+        "events",
+        "Events the contract emits, decoded from a [`crate::Event`].",
+        "use crate::bindings::{event_param, ContractEvent};\nuse crate::Event;",
+        &code,
+    );
+
+    let mut code = String::new();
+    for error in items(abi, "error") {
+        error_binding(&mut code, error, &mut structs);
+    }
+    module(
+        &mut out,
+        "errors",
+        "Custom errors the contract reverts with, decoded from a [`crate::Revert`].",
+        "use crate::bindings::ContractError;\n\
+         use crate::Revert;\n\
+         use web3::contract::tokens::Tokenizable;",
+        &code,
+    );
+
+    module(
+        &mut out,
+        "structs",
+        "Solidity structs used as parameters.",
+        "use web3::contract::tokens::{Tokenizable, TokenizableItem};\n\
+         use web3::contract::Error;\n\
+         use web3::ethabi::Token;",
+        &structs.into_values().collect::<String>(),
+    );
+
+    out
+}
+
+fn items<'a>(abi: &'a [Value], kind: &'a str) -> impl Iterator<Item = &'a Value> {
+    abi.iter().filter(move |item| item["type"] == kind)
+}
+
+fn module(out: &mut String, name: &str, doc: &str, imports: &str, code: &str) {
+    writeln!(out, "/// {}", doc).unwrap();
+    writeln!(out, "pub mod {} {{", name).unwrap();
+    if !code.is_empty() {
+        writeln!(out, "#[allow(unused_imports)]\nuse super::structs;").unwrap();
+        for import in imports.lines() {
+            writeln!(out, "#[allow(unused_imports)]\n{}", import).unwrap();
+        }
+        writeln!(
+            out,
+            "#[allow(unused_imports)]\nuse web3::types::{{Address, Bytes, U256}};"
+        )
+        .unwrap();
+        out.push_str(code);
+    }
+    out.push_str("}\n\n");
+}
+
+fn function_binding(
+    out: &mut String,
+    name: &str,
+    function: &Value,
+    structs: &mut BTreeMap<String, String>,
+) {
+    let inputs = params(function, "inputs");
+    let outputs = params(function, "outputs");
+    let fields = fields(inputs, structs);
+    let output_types: Vec<_> = outputs.iter().map(|p| rust_type(p, structs)).collect();
+    let output = match output_types.len() {
+        1 => output_types[0].clone(),
+        _ => format!("({})", output_types.join(", ")),
+    };
+    let returns = match outputs.len() {
+        0 => String::new(),
+        _ => format!(" returns ({})", signature_types(outputs).join(", ")),
+    };
+
+    writeln!(
+        out,
+        "/// `{}({}) {}{}`",
+        function["name"].as_str().unwrap(),
+        signature_types(inputs).join(", "),
+        function["stateMutability"].as_str().unwrap_or("nonpayable"),
+        returns
+    )
+    .unwrap();
+    struct_def(out, name, &fields);
+    writeln!(out, "impl ContractCall for {} {{", name).unwrap();
+    writeln!(
+        out,
+        "const NAME: &'static str = {:?};\nconst SIGNATURE: &'static str = {:?};\ntype Output = {};",
+        function["name"].as_str().unwrap(),
+        signature(function),
+        output
+    )
+    .unwrap();
+    writeln!(out, "fn into_tokens(self) -> Vec<Token> {{").unwrap();
+    let tokens: Vec<_> = fields
+        .iter()
+        .map(|(field, _)| format!("self.{}.into_token()", field))
+        .collect();
+    writeln!(out, "vec![{}]\n}}", tokens.join(", ")).unwrap();
+    let decode = match outputs.len() {
+        0 => "let _ = tokens;\nOk(())",
+        _ => "Detokenize::from_tokens(tokens)",
+    };
+    writeln!(
+        out,
+        "fn decode_output(tokens: Vec<Token>) -> Result<Self::Output, web3::contract::Error> {{\n{}\n}}\n}}\n",
+        decode
+    )
+    .unwrap();
+}
+
+fn event_binding(out: &mut String, event: &Value, structs: &mut BTreeMap<String, String>) {
+    let name = event["name"].as_str().unwrap();
+    let inputs = params(event, "inputs");
+    let fields = fields(inputs, structs);
+    let declared: Vec<_> = inputs
+        .iter()
+        .map(|p| {
+            let indexed = if p["indexed"] == true { " indexed" } else { "" };
+            format!(
+                "{}{} {}",
+                signature_type(p),
+                indexed,
+                p["name"].as_str().unwrap_or("")
+            )
+        })
+        .collect();
+
+    writeln!(out, "/// `{}({})`", name, declared.join(", ")).unwrap();
+    let rust_name = pascal_case(name);
+    struct_def(out, &rust_name, &fields);
+    writeln!(out, "impl ContractEvent for {} {{", rust_name).unwrap();
+    writeln!(out, "const NAME: &'static str = {:?};", name).unwrap();
+    writeln!(out, "fn from_event(event: &Event) -> Option<Self> {{").unwrap();
+    writeln!(out, "if event.name != Self::NAME {{\nreturn None;\n}}").unwrap();
+    let values: Vec<_> = fields
+        .iter()
+        .zip(inputs)
+        .map(|((field, _), p)| {
+            format!(
+                "{}: event_param(event, {:?})?",
+                field,
+                p["name"].as_str().unwrap_or("")
+            )
+        })
+        .collect();
+    writeln!(
+        out,
+        "Some({} {{ {} }})\n}}\n}}\n",
+        rust_name,
+        values.join(", ")
+    )
+    .unwrap();
+}
+
+fn error_binding(out: &mut String, error: &Value, structs: &mut BTreeMap<String, String>) {
+    let name = error["name"].as_str().unwrap();
+    let inputs = params(error, "inputs");
+    let fields = fields(inputs, structs);
+
+    writeln!(out, "/// `{}`", signature(error)).unwrap();
+    let rust_name = pascal_case(name);
+    struct_def(out, &rust_name, &fields);
+    writeln!(out, "impl ContractError for {} {{", rust_name).unwrap();
+    writeln!(out, "const NAME: &'static str = {:?};", name).unwrap();
+    writeln!(out, "fn from_revert(revert: &Revert) -> Option<Self> {{").unwrap();
+    writeln!(
+        out,
+        "let params = match revert {{\nRevert::Custom {{ name, params }} if name == Self::NAME && params.len() == {} => params,\n_ => return None,\n}};",
+        fields.len()
+    )
+    .unwrap();
+    let values: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, (field, _))| {
+            format!(
+                "{}: Tokenizable::from_token(params[{}].clone()).ok()?",
+                field, i
+            )
+        })
+        .collect();
+    writeln!(out, "let _ = params;").unwrap();
+    writeln!(
+        out,
+        "Some({} {{ {} }})\n}}\n}}\n",
+        rust_name,
+        values.join(", ")
+    )
+    .unwrap();
+}
+
+/// Generates the struct behind a `tuple` parameter, once per struct name.
+fn tuple_struct(param: &Value, structs: &mut BTreeMap<String, String>) -> String {
+    let internal = param["internalType"].as_str().unwrap_or_default();
+    let name = internal
+        .trim_start_matches("struct ")
+        .trim_end_matches("[]")
+        .rsplit('.')
+        .next()
+        .filter(|_| internal.starts_with("struct "))
+        .unwrap_or_else(|| panic!("tuple parameter without a struct name: {}", param))
+        .to_string();
+    if structs.contains_key(&name) {
+        return name;
+    }
+    structs.insert(name.clone(), String::new());
+
+    let components = param["components"].as_array().unwrap();
+    let fields = fields(components, structs);
+    let mut out = String::new();
+    writeln!(out, "/// `{}`", internal.trim_end_matches("[]")).unwrap();
+    struct_def(&mut out, &name, &fields);
+    writeln!(out, "impl Tokenizable for {} {{", name).unwrap();
+    writeln!(out, "fn from_token(token: Token) -> Result<Self, Error> {{").unwrap();
+    writeln!(
+        out,
+        "let mut tokens = match token {{\nToken::Tuple(tokens) if tokens.len() == {} => tokens.into_iter(),\nother => return Err(Error::InvalidOutputType(format!(\"Expected `{}`, got {{:?}}\", other))),\n}};",
+        fields.len(),
+        name
+    )
+    .unwrap();
+    let values: Vec<_> = fields
+        .iter()
+        .map(|(field, _)| {
+            format!(
+                "{}: Tokenizable::from_token(tokens.next().unwrap())?",
+                field
+            )
+        })
+        .collect();
+    writeln!(out, "Ok({} {{ {} }})\n}}", name, values.join(", ")).unwrap();
+    let tokens: Vec<_> = fields
+        .iter()
+        .map(|(field, _)| format!("self.{}.into_token()", field))
+        .collect();
+    writeln!(
+        out,
+        "fn into_token(self) -> Token {{\nToken::Tuple(vec![{}])\n}}\n}}\n",
+        tokens.join(", ")
+    )
+    .unwrap();
+    writeln!(out, "impl TokenizableItem for {} {{}}\n", name).unwrap();
+
+    structs.insert(name.clone(), out);
+    name
+}
+
+fn struct_def(out: &mut String, name: &str, fields: &[(String, String)]) {
+    if fields.is_empty() {
+        // `Default` lets code generic over the bindings build calls without arguments
+        writeln!(out, "#[derive(Clone, Debug, Default, PartialEq)]").unwrap();
+        writeln!(out, "pub struct {};\n", name).unwrap();
+        return;
+    }
+    writeln!(out, "#[derive(Clone, Debug, PartialEq)]").unwrap();
+    writeln!(out, "pub struct {} {{", name).unwrap();
+    for (field, ty) in fields {
+        writeln!(out, "pub {}: {},", field, ty).unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+}
+
+/// `(field name, Rust type)` of each parameter; unnamed ones are numbered.
+fn fields(params: &[Value], structs: &mut BTreeMap<String, String>) -> Vec<(String, String)> {
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let name = snake_case(p["name"].as_str().unwrap_or_default());
+            let name = match name.as_str() {
+                "" => format!("arg{}", i),
+                "as" | "async" | "await" | "break" | "const" | "crate" | "else" | "enum" | "fn"
+                | "for" | "if" | "impl" | "in" | "let" | "loop" | "match" | "mod" | "move"
+                | "mut" | "pub" | "ref" | "return" | "self" | "static" | "struct" | "super"
+                | "trait" | "type" | "use" | "where" | "while" => format!("{}_", name),
+                _ => name,
+            };
+            (name, rust_type(p, structs))
+        })
+        .collect()
+}
+
+fn rust_type(param: &Value, structs: &mut BTreeMap<String, String>) -> String {
+    let kind = param["type"].as_str().unwrap();
+    if let Some(item) = kind.strip_suffix("[]") {
+        let mut item_param = param.clone();
+        item_param["type"] = Value::String(item.to_string());
+        return format!("Vec<{}>", rust_type(&item_param, structs));
+    }
+    if kind.ends_with(']') {
+        panic!("fixed-size array parameters are not supported: {}", param);
+    }
+
+    match kind {
+        "address" => "Address".to_string(),
+        "bool" => "bool".to_string(),
+        "string" => "String".to_string(),
+        "bytes" => "Bytes".to_string(),
+        "tuple" => format!("structs::{}", tuple_struct(param, structs)),
+        _ if kind.starts_with("uint") || kind.starts_with("int") => "U256".to_string(),
+        _ if kind.starts_with("bytes") => format!("[u8; {}]", &kind["bytes".len()..]),
+        _ => panic!("unsupported ABI type: {}", kind),
+    }
+}
+
+/// Canonical signature, e.g. `safeTransferFrom(address,address,uint256,bytes)`.
+fn signature(item: &Value) -> String {
+    format!(
+        "{}({})",
+        item["name"].as_str().unwrap(),
+        signature_types(params(item, "inputs")).join(",")
+    )
+}
+
+fn signature_types(params: &[Value]) -> Vec<String> {
+    params.iter().map(signature_type).collect()
+}
+
+fn signature_type(param: &Value) -> String {
+    let kind = param["type"].as_str().unwrap();
+    match kind.strip_prefix("tuple") {
+        Some(suffix) => format!(
+            "({}){}",
+            signature_types(params(param, "components")).join(","),
+            suffix
+        ),
+        None => kind.to_string(),
+    }
+}
+
+fn params<'a>(item: &'a Value, key: &str) -> &'a [Value] {
+    item[key].as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// `tokenURI` -> `token_uri`, `_data` -> `data`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.trim_start_matches('_').chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// `tokenURI` -> `TokenUri`, `URI` -> `Uri`.
+fn pascal_case(name: &str) -> String {
+    snake_case(name)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
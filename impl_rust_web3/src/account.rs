@@ -0,0 +1,285 @@
+use crate::signer::{decrypt_keystore, LocalSigner};
+use crate::{parse_address, Error, Web3Result};
+use secp256k1::SecretKey;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use web3::signing::{Key, SecretKeyRef};
+use web3::types::Address;
+
+pub const DEFAULT_KEYSTORE_DIR: &str = "keystore";
+
+/// Hidden so geth, which also scans the directory, skips it.
+const ALIASES_FILE: &str = ".aliases.json";
+
+/// An encrypted key in a [`Keystore`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Account {
+    pub address: Address,
+    pub alias: Option<String>,
+    pub path: PathBuf,
+}
+
+impl Account {
+    pub fn signer(&self, password: &str) -> Web3Result<LocalSigner> {
+        LocalSigner::from_keystore(&self.path, password)
+    }
+}
+
+/// A directory of Web3 Secret Storage files, laid out like geth's `keystore` directory
+/// so the two can share it. Aliases are kept next to the keys in a hidden file.
+#[derive(Clone, Debug)]
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Web3Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        Ok(Keystore { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Every key file in the directory, ordered by file name (i.e. creation time).
+    pub fn accounts(&self) -> Web3Result<Vec<Account>> {
+        let aliases = self.aliases()?;
+        let mut paths: Vec<PathBuf> = fs::read_dir(&self.dir)
+            .map_err(|e| io_error(&self.dir, e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && !is_hidden(path))
+            .collect();
+        paths.sort();
+
+        Ok(paths
+            .into_iter()
+            .filter_map(|path| {
+                let address = read_address(&path)?;
+                let alias = aliases
+                    .iter()
+                    .find(|(_, a)| **a == address)
+                    .map(|(alias, _)| alias.clone());
+                Some(Account {
+                    address,
+                    alias,
+                    path,
+                })
+            })
+            .collect())
+    }
+
+    /// Looks an account up by `0x` address or alias.
+    pub fn find(&self, account: &str) -> Web3Result<Account> {
+        let address = parse_address(account.to_string());
+        self.accounts()?
+            .into_iter()
+            .find(|a| Some(a.address) == address || a.alias.as_deref() == Some(account))
+            .ok_or_else(|| {
+                Error::Signing(format!("no account {} in {}", account, self.dir.display()))
+            })
+    }
+
+    /// Generates a new key encrypted with `password`.
+    pub fn create(&self, password: &str, alias: Option<&str>) -> Web3Result<Account> {
+        let key = SecretKey::new(&mut secp256k1::rand::thread_rng());
+        self.store(&key, password, alias)
+    }
+
+    /// Stores an existing hex encoded private key encrypted with `password`.
+    pub fn import(&self, secret: &str, password: &str, alias: Option<&str>) -> Web3Result<Account> {
+        let key = SecretKey::from_str(secret.trim().trim_start_matches("0x"))
+            .map_err(|e| Error::Signing(format!("invalid private key: {}", e)))?;
+        let address = SecretKeyRef::new(&key).address();
+        if self.accounts()?.iter().any(|a| a.address == address) {
+            return Err(Error::Signing(format!(
+                "account {:?} already exists",
+                address
+            )));
+        }
+        self.store(&key, password, alias)
+    }
+
+    /// Re-encrypts the key of `account` under `new_password`, replacing the file in place.
+    pub fn change_password(
+        &self,
+        account: &Account,
+        password: &str,
+        new_password: &str,
+    ) -> Web3Result<()> {
+        let secret = decrypt_keystore(&account.path, password)?;
+
+        // write next to the old file first so a failure never loses the key
+        let name = file_name(&account.path);
+        let tmp = format!(".{}.tmp", name);
+        self.encrypt(&secret, new_password, &tmp)?;
+        fs::rename(self.dir.join(&tmp), &account.path).map_err(|e| io_error(&account.path, e))
+    }
+
+    /// Points `alias` at `address`, replacing what it pointed at before.
+    pub fn set_alias(&self, alias: &str, address: Address) -> Web3Result<()> {
+        if parse_address(alias.to_string()).is_some() {
+            return Err(Error::Signing(format!(
+                "alias {} looks like an address",
+                alias
+            )));
+        }
+        let mut aliases = self.aliases()?;
+        aliases.insert(alias.to_string(), address);
+
+        let aliases: BTreeMap<&String, String> = aliases
+            .iter()
+            .map(|(k, v)| (k, format!("{:?}", v)))
+            .collect();
+        let path = self.dir.join(ALIASES_FILE);
+        let json = serde_json::to_string_pretty(&aliases)
+            .map_err(|e| Error::Internal(format!("failed to encode aliases: {}", e)))?;
+        fs::write(&path, json).map_err(|e| io_error(&path, e))
+    }
+
+    fn aliases(&self) -> Web3Result<BTreeMap<String, Address>> {
+        let path = self.dir.join(ALIASES_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let text = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+        let aliases: BTreeMap<String, String> = serde_json::from_str(&text)
+            .map_err(|e| Error::Decode(format!("{}: {}", path.display(), e)))?;
+
+        Ok(aliases
+            .into_iter()
+            .filter_map(|(alias, address)| Some((alias, parse_address(address)?)))
+            .collect())
+    }
+
+    fn store(&self, key: &SecretKey, password: &str, alias: Option<&str>) -> Web3Result<Account> {
+        let address = SecretKeyRef::new(key).address();
+        // geth's naming: UTC--<created at>--<address without 0x>
+        let name = format!(
+            "UTC--{}--{}",
+            chrono::Utc::now().format("%Y-%m-%dT%H-%M-%S%.9fZ"),
+            hex::encode(address.as_bytes())
+        );
+        self.encrypt(&key[..], password, &name)?;
+        if let Some(alias) = alias {
+            self.set_alias(alias, address)?;
+        }
+
+        Ok(Account {
+            address,
+            alias: alias.map(|a| a.to_string()),
+            path: self.dir.join(name),
+        })
+    }
+
+    fn encrypt(&self, secret: &[u8], password: &str, name: &str) -> Web3Result<()> {
+        eth_keystore::encrypt_key(
+            &self.dir,
+            &mut rand::thread_rng(),
+            secret,
+            password,
+            Some(name),
+        )
+        .map_err(|e| Error::Signing(format!("failed to write keystore: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Reads the `address` field geth writes into every key file.
+fn read_address(path: &Path) -> Option<Address> {
+    let json: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    json.get("crypto").or_else(|| json.get("Crypto"))?;
+    parse_address(json.get("address")?.as_str()?.to_string())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn is_hidden(path: &Path) -> bool {
+    file_name(path).starts_with('.')
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> Error {
+    Error::Internal(format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::Signer;
+
+    const SECRET: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const ADDRESS: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+
+    /// An empty keystore in the temp dir, unique to this test run.
+    fn keystore(name: &str) -> Keystore {
+        let dir = std::env::temp_dir().join(format!("keystore-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        Keystore::open(dir).unwrap()
+    }
+
+    fn address() -> Address {
+        parse_address(ADDRESS.to_string()).unwrap()
+    }
+
+    #[test]
+    fn finds_accounts_by_address_and_alias() {
+        let keystore = keystore("find");
+        let imported = keystore.import(SECRET, "secret", Some("main")).unwrap();
+        let created = keystore.create("secret", None).unwrap();
+
+        assert_eq!(imported.address, address());
+        assert_eq!(keystore.accounts().unwrap().len(), 2);
+        assert_eq!(keystore.find(ADDRESS).unwrap(), imported);
+        assert_eq!(keystore.find("main").unwrap(), imported);
+        assert_eq!(
+            keystore.find(&format!("{:?}", created.address)).unwrap(),
+            created
+        );
+        assert!(matches!(keystore.find("other"), Err(Error::Signing(_))));
+    }
+
+    #[test]
+    fn rejects_a_duplicate_import() {
+        let keystore = keystore("duplicate");
+        keystore.import(SECRET, "secret", None).unwrap();
+
+        match keystore.import(SECRET.trim_start_matches("0x"), "other", None) {
+            Err(Error::Signing(message)) => assert!(message.contains("already exists")),
+            other => panic!("expected a duplicate error, got {:?}", other),
+        }
+        assert_eq!(keystore.accounts().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_an_alias_that_looks_like_an_address() {
+        let keystore = keystore("alias");
+
+        assert!(matches!(
+            keystore.set_alias(ADDRESS, address()),
+            Err(Error::Signing(_))
+        ));
+        keystore.set_alias("deployer", address()).unwrap();
+        keystore.import(SECRET, "secret", None).unwrap();
+        assert_eq!(keystore.find("deployer").unwrap().address, address());
+    }
+
+    #[test]
+    fn changes_the_password() {
+        let keystore = keystore("password");
+        let account = keystore.import(SECRET, "old", None).unwrap();
+
+        keystore.change_password(&account, "old", "new").unwrap();
+
+        assert!(account.signer("old").is_err());
+        assert_eq!(account.signer("new").unwrap().address(), address());
+        assert_eq!(keystore.accounts().unwrap(), vec![account]);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::bindings::{self, ContractCall};
+use crate::{Error, Revert, Web3Result};
+use std::marker::PhantomData;
+use web3::contract::Contract;
+use web3::ethabi::{self, Function, ParamType, Token};
+use web3::transports::{Batch, Http};
+use web3::types::{Address, Bytes, CallRequest};
+use web3::Web3;
+
+/// Multicall3 is deployed at the same address on most EVM chains.
+/// See https://github.com/mds1/multicall
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// How a [`ReadBatch`] is sent to the node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchMode {
+    /// One JSON-RPC batch request holding an `eth_call` per read.
+    Rpc,
+    /// A single `eth_call` to Multicall3 `aggregate3` at the given address.
+    Multicall(Address),
+}
+
+impl BatchMode {
+    pub fn multicall() -> Self {
+        BatchMode::Multicall(MULTICALL3_ADDRESS.parse().unwrap())
+    }
+}
+
+/// Identifies one call in a [`ReadBatch`] and the type its result decodes to.
+#[derive(Debug)]
+pub struct Pending<C> {
+    index: usize,
+    _call: PhantomData<C>,
+}
+
+#[derive(Clone, Debug)]
+struct Call {
+    target: Address,
+    function: Function,
+    data: Bytes,
+}
+
+/// Groups read-only contract calls so they are answered in one round trip.
+#[derive(Clone, Debug, Default)]
+pub struct ReadBatch {
+    calls: Vec<Call>,
+}
+
+impl ReadBatch {
+    pub fn new() -> Self {
+        ReadBatch::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Queues `call` on `contract`; the handle reads its result after `execute`.
+    pub fn add<C: ContractCall>(
+        &mut self,
+        contract: &Contract<Http>,
+        call: C,
+    ) -> Web3Result<Pending<C>> {
+        let (function, data) = bindings::encode(contract.abi(), call)?;
+        self.calls.push(Call {
+            target: contract.address(),
+            function: function.clone(),
+            data: Bytes(data),
+        });
+
+        Ok(Pending {
+            index: self.calls.len() - 1,
+            _call: PhantomData,
+        })
+    }
+
+    /// Sends every queued call. A failing call only fails its own result.
+    pub async fn execute(self, web3: &Web3<Http>, mode: BatchMode) -> Web3Result<BatchResults> {
+        let outputs = match mode {
+            BatchMode::Rpc => self.execute_rpc(web3).await?,
+            BatchMode::Multicall(address) => self.execute_multicall(web3, address).await?,
+        };
+
+        let results = self
+            .calls
+            .iter()
+            .zip(outputs)
+            .map(|(call, output)| Ok(call.function.decode_output(&output?.0)?))
+            .collect();
+
+        Ok(BatchResults { results })
+    }
+
+    async fn execute_rpc(&self, web3: &Web3<Http>) -> Web3Result<Vec<Web3Result<Bytes>>> {
+        let transport = Batch::new(web3.transport().clone());
+        let eth = Web3::new(transport.clone()).eth();
+        let pending: Vec<_> = self
+            .calls
+            .iter()
+            .map(|call| eth.call(call.request(), None))
+            .collect();
+        transport.submit_batch().await?;
+
+        let mut outputs = Vec::with_capacity(pending.len());
+        for output in pending {
+            outputs.push(output.await.map_err(Error::from));
+        }
+        Ok(outputs)
+    }
+
+    async fn execute_multicall(
+        &self,
+        web3: &Web3<Http>,
+        address: Address,
+    ) -> Web3Result<Vec<Web3Result<Bytes>>> {
+        let calls = self
+            .calls
+            .iter()
+            .map(|call| {
+                Token::Tuple(vec![
+                    Token::Address(call.target),
+                    Token::Bool(true),
+                    Token::Bytes(call.data.0.clone()),
+                ])
+            })
+            .collect();
+        let mut data = ethabi::short_signature("aggregate3", &[aggregate3_calls()]).to_vec();
+        data.extend(ethabi::encode(&[Token::Array(calls)]));
+
+        let request = CallRequest {
+            to: Some(address),
+            data: Some(Bytes(data)),
+            ..Default::default()
+        };
+        let output = web3.eth().call(request, None).await?;
+        // a call to an address without code succeeds with no output
+        if output.0.is_empty() {
+            return Err(Error::Decode(format!(
+                "no Multicall3 contract at {:?}",
+                address
+            )));
+        }
+
+        let results = ethabi::decode(&[aggregate3_results()], &output.0)?;
+        let results = match results.into_iter().next() {
+            Some(Token::Array(results)) if results.len() == self.calls.len() => results,
+            _ => return Err(Error::Decode("unexpected aggregate3 result".to_string())),
+        };
+
+        Ok(results
+            .into_iter()
+            .map(|result| match result {
+                Token::Tuple(fields) => match fields.as_slice() {
+                    [Token::Bool(true), Token::Bytes(data)] => Ok(Bytes(data.clone())),
+                    [Token::Bool(false), Token::Bytes(data)] => {
+                        Err(Error::Revert(Revert::decode(data)))
+                    }
+                    _ => Err(Error::Decode("unexpected aggregate3 result".to_string())),
+                },
+                _ => Err(Error::Decode("unexpected aggregate3 result".to_string())),
+            })
+            .collect())
+    }
+}
+
+impl Call {
+    fn request(&self) -> CallRequest {
+        CallRequest {
+            to: Some(self.target),
+            data: Some(self.data.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Decoded outputs of an executed [`ReadBatch`], in the order the calls were added.
+#[derive(Debug)]
+pub struct BatchResults {
+    results: Vec<Web3Result<Vec<Token>>>,
+}
+
+impl BatchResults {
+    /// Takes the result of `pending`, decoded to its type.
+    pub fn take<C: ContractCall>(&mut self, pending: Pending<C>) -> Web3Result<C::Output> {
+        let result = self
+            .results
+            .get_mut(pending.index)
+            .map(|r| std::mem::replace(r, Ok(vec![])))
+            .ok_or_else(|| Error::Internal("call is not part of this batch".to_string()))?;
+
+        Ok(C::decode_output(result?)?)
+    }
+}
+
+/// `(address target, bool allowFailure, bytes callData)[]`
+fn aggregate3_calls() -> ParamType {
+    ParamType::Array(Box::new(ParamType::Tuple(vec![
+        ParamType::Address,
+        ParamType::Bool,
+        ParamType::Bytes,
+    ])))
+}
+
+/// `(bool success, bytes returnData)[]`
+fn aggregate3_results() -> ParamType {
+    ParamType::Array(Box::new(ParamType::Tuple(vec![
+        ParamType::Bool,
+        ParamType::Bytes,
+    ])))
+}
@@ -0,0 +1,81 @@
+use crate::{Error, Event, Revert, Web3Result};
+use web3::contract::tokens::Tokenizable;
+use web3::contract::Contract;
+use web3::ethabi::{self, Function, Token};
+use web3::transports::Http;
+use web3::types::{Bytes, CallRequest};
+use web3::Web3;
+
+/// A call to a contract function. Implemented by the types `build.rs` generates from
+/// each `abi.json`, e.g. [`crate::nft_721::bindings::functions`].
+pub trait ContractCall {
+    const NAME: &'static str;
+    /// Canonical signature, e.g. `safeTransferFrom(address,address,uint256,bytes)`, which
+    /// tells overloads apart.
+    const SIGNATURE: &'static str;
+    type Output;
+
+    fn into_tokens(self) -> Vec<Token>;
+
+    fn decode_output(tokens: Vec<Token>) -> Result<Self::Output, web3::contract::Error>;
+}
+
+/// An event decoded from a log of the contract.
+pub trait ContractEvent: Sized {
+    const NAME: &'static str;
+
+    /// `None` when `event` is another event or its params do not match.
+    fn from_event(event: &Event) -> Option<Self>;
+}
+
+/// A custom error the contract reverts with.
+pub trait ContractError: Sized {
+    const NAME: &'static str;
+
+    /// `None` when `revert` is not this error.
+    fn from_revert(revert: &Revert) -> Option<Self>;
+}
+
+/// The function `C` calls, among the overloads in `abi`.
+pub(crate) fn function<C: ContractCall>(abi: &ethabi::Contract) -> Web3Result<&Function> {
+    abi.functions_by_name(C::NAME)?
+        .iter()
+        .find(|f| signature(f) == C::SIGNATURE)
+        .ok_or_else(|| Error::Abi(ethabi::Error::InvalidName(C::SIGNATURE.to_string())))
+}
+
+/// Encodes `call` as transaction data for `abi`.
+pub(crate) fn encode<C: ContractCall>(
+    abi: &ethabi::Contract,
+    call: C,
+) -> Web3Result<(&Function, Vec<u8>)> {
+    let function = function::<C>(abi)?;
+    let data = function.encode_input(&call.into_tokens())?;
+    Ok((function, data))
+}
+
+/// Runs `call` on `contract` with `eth_call` at the latest block.
+pub async fn query<C: ContractCall>(
+    web3: &Web3<Http>,
+    contract: &Contract<Http>,
+    call: C,
+) -> Web3Result<C::Output> {
+    let (function, data) = encode(contract.abi(), call)?;
+    let request = CallRequest {
+        to: Some(contract.address()),
+        data: Some(Bytes(data)),
+        ..Default::default()
+    };
+    let output = web3.eth().call(request, None).await?;
+
+    Ok(C::decode_output(function.decode_output(&output.0)?)?)
+}
+
+pub(crate) fn event_param<T: Tokenizable>(event: &Event, name: &str) -> Option<T> {
+    T::from_token(event.param(name)?.clone()).ok()
+}
+
+fn signature(function: &Function) -> String {
+    let types: Vec<_> = function.inputs.iter().map(|p| p.kind.to_string()).collect();
+    format!("{}({})", function.name, types.join(","))
+}
@@ -0,0 +1,185 @@
+use crate::Web3Result;
+use prelude::config::GasPolicy;
+use prelude::FeeSpeed;
+use web3::transports::Http;
+use web3::types::{BlockNumber, TransactionParameters, U256, U64};
+use web3::Web3;
+
+const FEE_HISTORY_BLOCKS: u64 = 10;
+const MIN_PRIORITY_FEE: u64 = 1_000_000_000;
+/// JSON-RPC error code for a method the node does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fee {
+    Legacy {
+        gas_price: U256,
+    },
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl Fee {
+    pub fn apply(&self, tx: &mut TransactionParameters) {
+        match *self {
+            Fee::Legacy { gas_price } => {
+                tx.transaction_type = None;
+                tx.gas_price = Some(gas_price);
+                tx.max_fee_per_gas = None;
+                tx.max_priority_fee_per_gas = None;
+            }
+            Fee::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                tx.transaction_type = Some(U64::from(2));
+                tx.gas_price = None;
+                tx.max_fee_per_gas = Some(max_fee_per_gas);
+                tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            }
+        }
+    }
+
+    /// The most that can be charged per unit of gas.
+    pub fn max_per_gas(&self) -> U256 {
+        match *self {
+            Fee::Legacy { gas_price } => gas_price,
+            Fee::Eip1559 {
+                max_fee_per_gas, ..
+            } => max_fee_per_gas,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeStrategy {
+    pub speed: FeeSpeed,
+    pub gas_price: Option<U256>,
+    pub max_fee_cap: Option<U256>,
+    pub max_priority_fee_cap: Option<U256>,
+}
+
+impl FeeStrategy {
+    pub fn new(policy: &GasPolicy) -> Self {
+        FeeStrategy {
+            speed: policy.speed,
+            gas_price: policy.price.map(U256::from),
+            max_fee_cap: policy.max_fee_cap.map(U256::from),
+            max_priority_fee_cap: policy.max_priority_fee_cap.map(U256::from),
+        }
+    }
+
+    /// Prices a transaction from recent blocks. Type-2 fees are used when `eip1559` is set
+    /// and the node reports a base fee; otherwise this falls back to a legacy gas price.
+    pub async fn estimate(&self, web3: &Web3<Http>, eip1559: bool) -> Web3Result<Fee> {
+        if eip1559 {
+            if let Some(fee) = self.dynamic_fee(web3).await? {
+                return Ok(fee);
+            }
+        }
+        self.legacy_fee(web3).await
+    }
+
+    /// `None` when the node has no `eth_feeHistory` or no base fee; any other failure,
+    /// e.g. a timeout, is returned rather than silently priced as legacy.
+    async fn dynamic_fee(&self, web3: &Web3<Http>) -> Web3Result<Option<Fee>> {
+        let history = match web3
+            .eth()
+            .fee_history(
+                U256::from(FEE_HISTORY_BLOCKS),
+                BlockNumber::Latest,
+                Some(vec![reward_percentile(self.speed)]),
+            )
+            .await
+        {
+            Ok(history) => history,
+            Err(e) if is_method_unsupported(&e) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // the last entry is the base fee of the next block; zero means London is not active
+        let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+        if base_fee.is_zero() {
+            return Ok(None);
+        }
+
+        let rewards: Vec<U256> = history
+            .reward
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|r| r.first().copied())
+            .filter(|r| !r.is_zero())
+            .collect();
+        let priority_fee = if rewards.is_empty() {
+            U256::zero()
+        } else {
+            rewards.iter().fold(U256::zero(), |acc, r| acc + r) / rewards.len()
+        };
+        let priority_fee = cap(
+            priority_fee.max(U256::from(MIN_PRIORITY_FEE)),
+            self.max_priority_fee_cap,
+        );
+        let max_fee = cap(base_fee * 2 + priority_fee, self.max_fee_cap).max(priority_fee);
+
+        Ok(Some(Fee::Eip1559 {
+            max_fee_per_gas: max_fee,
+            max_priority_fee_per_gas: priority_fee,
+        }))
+    }
+
+    async fn legacy_fee(&self, web3: &Web3<Http>) -> Web3Result<Fee> {
+        let gas_price = match self.gas_price {
+            Some(gas_price) => gas_price,
+            None => {
+                let gas_price = web3.eth().gas_price().await?;
+                gas_price * legacy_percent(self.speed) / 100
+            }
+        };
+
+        Ok(Fee::Legacy {
+            gas_price: cap(gas_price, self.max_fee_cap),
+        })
+    }
+}
+
+fn reward_percentile(speed: FeeSpeed) -> f64 {
+    match speed {
+        FeeSpeed::Slow => 10.0,
+        FeeSpeed::Standard => 50.0,
+        FeeSpeed::Fast => 90.0,
+    }
+}
+
+fn legacy_percent(speed: FeeSpeed) -> u64 {
+    match speed {
+        FeeSpeed::Slow => 90,
+        FeeSpeed::Standard => 100,
+        FeeSpeed::Fast => 125,
+    }
+}
+
+fn cap(value: U256, limit: Option<U256>) -> U256 {
+    match limit {
+        Some(limit) => value.min(limit),
+        None => value,
+    }
+}
+
+/// Whether the node rejected the call because it does not implement the method. Geth
+/// answers -32601; some other nodes use a generic code with a "method ..." message.
+fn is_method_unsupported(e: &web3::Error) -> bool {
+    match e {
+        web3::Error::Rpc(rpc) => {
+            let message = rpc.message.to_lowercase();
+            rpc.code.code() == METHOD_NOT_FOUND
+                || (message.contains("method")
+                    && (message.contains("not found")
+                        || message.contains("does not exist")
+                        || message.contains("not supported")
+                        || message.contains("not available")))
+        }
+        _ => false,
+    }
+}
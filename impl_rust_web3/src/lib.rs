@@ -1,144 +1,544 @@
+use crate::bindings::ContractCall;
+use crate::fee::FeeStrategy;
+use crate::nonce::NonceManager;
+pub use crate::outcome::{Event, GasLimit, MintOutcome, Minted, TxOutcome};
+pub use crate::pending::PendingTx;
+pub use crate::revert::Revert;
+use crate::signer::Signer;
+use prelude::config::{NetworkConfig, WaitPolicy};
 use prelude::*;
-use secp256k1::SecretKey;
-use std::str::FromStr;
-use std::{env, time};
-use web3::contract::{Contract, Options};
-use web3::signing::SecretKeyRef;
+use std::time;
+use web3::contract::tokens::Tokenize;
+use web3::contract::Contract;
+use web3::error::TransportError;
+use web3::ethabi;
 use web3::transports::Http;
-use web3::types::{Address, TransactionParameters, U256};
+pub use web3::types::{Address, H256, U256};
+use web3::types::{Bytes, CallRequest, TransactionParameters};
 use web3::Web3;
 
+pub mod account;
+pub mod batch;
+pub mod bindings;
+pub mod fee;
+pub mod nft;
 pub mod nft_1155;
 pub mod nft_721;
+pub mod nonce;
+mod outcome;
+mod pending;
+mod revert;
+pub mod signer;
+pub mod tx;
 
-fn contract(contract_address: Address, abi: &[u8], network: Network) -> Contract<Http> {
-    let transport = Http::new(&network.chain_url()).ok().unwrap();
-    Contract::from_json(Web3::new(transport).eth(), contract_address, abi).unwrap()
+/// Contract summary read by the NFT clients in a single batch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractInfo {
+    pub name: String,
+    pub latest_token_id: U256,
+    pub total_supply: U256,
+    pub total_owned: U256,
 }
 
-async fn deploy_contract(
-    wallet_secret: String,
+#[derive(Clone, Debug, Default)]
+pub struct TxOptions {
+    pub fee_speed: Option<FeeSpeed>,
+    pub gas_limit: Option<u64>,
+    /// Signs with this nonce instead of taking the next one from the [`NonceManager`].
+    pub nonce: Option<u64>,
+    /// Override the network's [`WaitPolicy`].
+    pub confirmations: Option<u64>,
+    pub poll_interval: Option<time::Duration>,
+    pub timeout: Option<time::Duration>,
+}
+
+impl TxOptions {
+    /// The network's wait policy with the overrides from these options applied.
+    pub fn wait_policy(&self, network: &NetworkConfig) -> WaitPolicy {
+        let mut policy = network.wait().clone();
+        if let Some(confirmations) = self.confirmations {
+            policy.confirmations = confirmations;
+        }
+        if let Some(poll_interval) = self.poll_interval {
+            policy.poll_interval = poll_interval;
+        }
+        if let Some(timeout) = self.timeout {
+            policy.timeout = timeout;
+        }
+        policy
+    }
+}
+
+/// Opens a JSON-RPC connection to `network`. Clones of the returned `Web3` share one
+/// pooled HTTP client, so keep it around instead of connecting per call.
+pub fn connect(network: &NetworkConfig) -> Web3Result<Web3<Http>> {
+    let rpc = network.rpc();
+    let client = reqwest::Client::builder()
+        .user_agent("web3.rs")
+        .timeout(rpc.timeout)
+        .connect_timeout(rpc.connect_timeout)
+        .pool_max_idle_per_host(rpc.pool_max_idle)
+        .build()
+        .map_err(|e| Error::Internal(format!("failed to build http client: {}", e)))?;
+    let url = network.chain_url().parse().map_err(|e| {
+        Error::Internal(format!("invalid chain_url {}: {}", network.chain_url(), e))
+    })?;
+
+    Ok(Web3::new(Http::with_client(client, url)))
+}
+
+fn contract(
+    web3: &Web3<Http>,
+    contract_address: Address,
     abi: &[u8],
-    network: Network,
-    bytecode: &str,
 ) -> Web3Result<Contract<Http>> {
-    let secret_key = SecretKey::from_str(&wallet_secret).unwrap();
-    let transport = Http::new(&network.chain_url()).ok().unwrap();
-    let contract = Contract::deploy(Web3::new(transport).eth(), abi)?
-        .confirmations(1)
-        .poll_interval(time::Duration::from_secs(10))
-        .options(Options::with(|opt| {
-            opt.gas = Some(U256::from(GAS_LIMIT));
-            opt.gas_price = Some(U256::from(GAS_PRICE));
-        }))
-        .sign_with_key_and_execute(
-            bytecode,
-            (),
-            SecretKeyRef::from(&secret_key),
-            Some(network.chain_id()),
-        )
-        .await?;
-
-    Ok(contract)
+    Ok(Contract::from_json(web3.eth(), contract_address, abi)?)
 }
 
-pub fn parse_address(address: String) -> Option<Address> {
-    match address.trim_start_matches("0x").parse() {
-        Ok(value) => Some(value),
-        Err(_e) => None,
+/// Fails with [`Error::UnsupportedInterface`] unless `contract` reports the interface id
+/// in `supports_interface` through ERC-165 `supportsInterface`. Contracts without
+/// ERC-165, and addresses without code, count as not supporting it.
+async fn ensure_interface<C: ContractCall<Output = bool>>(
+    web3: &Web3<Http>,
+    contract: &Contract<Http>,
+    supports_interface: C,
+    schema: Schema,
+) -> Web3Result<()> {
+    let supported = bindings::query(web3, contract, supports_interface).await;
+
+    match supported {
+        Ok(true) => Ok(()),
+        Ok(false) | Err(Error::Revert(_)) | Err(Error::Decode(_)) | Err(Error::Abi(_)) => {
+            Err(Error::UnsupportedInterface {
+                address: contract.address(),
+                expected: schema,
+            })
+        }
+        Err(e) => Err(e),
     }
 }
 
-pub async fn get_balance(network: Network) -> Web3Result<()> {
-    let wallet_address = env::var("WALLET_ADDRESS").expect("WALLET_ADDRESS must be set");
-
-    let transport = Http::new(&network.chain_url())
-        .ok()
-        .expect("should set ethereum url");
-    let cli = Web3::new(transport);
+/// Uses the explicit limit from `options` or config when given, otherwise asks the node
+/// via `eth_estimateGas` and applies the configured safety multiplier.
+async fn gas_limit(
+    web3: &Web3<Http>,
+    network: &NetworkConfig,
+    from: Address,
+    tx: &TransactionParameters,
+    options: &TxOptions,
+) -> Web3Result<GasLimit> {
+    if let Some(limit) = options.gas_limit.or(network.gas().limit) {
+        return Ok(GasLimit {
+            limit: U256::from(limit),
+            estimate: None,
+        });
+    }
 
-    let balance = cli
+    let estimate = web3
         .eth()
-        .balance(parse_address(wallet_address).unwrap(), None)
+        .estimate_gas(call_request(from, tx), None)
         .await?;
+    let limit = estimate * U256::from((network.gas().multiplier * 100.0) as u64) / 100;
 
-    println!(
-        "balance: {:?}",
-        unit::to_ether(balance.to_string().as_str(), "wei")
-    );
+    Ok(GasLimit {
+        limit,
+        estimate: Some(estimate),
+    })
+}
 
+fn call_request(from: Address, tx: &TransactionParameters) -> CallRequest {
+    CallRequest {
+        from: Some(from),
+        to: tx.to,
+        value: Some(tx.value),
+        data: Some(tx.data.clone()),
+        ..Default::default()
+    }
+}
+
+/// Runs `tx` through `eth_call` against the latest block so a revert surfaces
+/// as [`Error::Revert`] before any gas is spent.
+async fn simulate(web3: &Web3<Http>, from: Address, tx: &TransactionParameters) -> Web3Result<()> {
+    web3.eth().call(call_request(from, tx), None).await?;
     Ok(())
 }
 
-pub async fn send_eth(network: Network, eth: f64, to: String) -> Web3Result<()> {
-    let to = parse_address(to.to_owned()).unwrap();
-    let wallet_secret = env::var("WALLET_SECRET").expect("WALLET_SECRET must be set");
-    let prev_key = SecretKey::from_str(&wallet_secret).unwrap();
+/// Fills in gas limit, fees and chain id for `tx`.
+async fn prepare_transaction(
+    web3: &Web3<Http>,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    mut tx: TransactionParameters,
+    options: &TxOptions,
+) -> Web3Result<(TransactionParameters, GasLimit)> {
+    simulate(web3, signer.address(), &tx).await?;
+    let gas = gas_limit(web3, network, signer.address(), &tx, options).await?;
+    tx.gas = gas.limit;
+
+    let mut strategy = FeeStrategy::new(network.gas());
+    if let Some(speed) = options.fee_speed {
+        strategy.speed = speed;
+    }
+    let fee = strategy.estimate(web3, network.network().eip1559).await?;
+    fee.apply(&mut tx);
+    tx.chain_id = Some(network.chain_id());
+
+    Ok((tx, gas))
+}
+
+/// Prepares, signs and broadcasts `tx`, returning its hash and gas limit without waiting
+/// for it to be mined. Unless `options` pins a nonce, one is taken from the shared [`NonceManager`]
+/// and handed back if the transaction never reaches the node.
+async fn submit_transaction(
+    web3: &Web3<Http>,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    tx: TransactionParameters,
+    options: &TxOptions,
+) -> Web3Result<(H256, GasLimit)> {
+    let (mut tx, gas) = prepare_transaction(web3, network, signer, tx, options).await?;
+    let (chain_id, from) = (network.chain_id(), signer.address());
 
-    let transport = Http::new(&network.chain_url())
-        .ok()
-        .expect("should set ethereum url");
-    let cli = Web3::new(transport);
+    let nonces = NonceManager::shared();
+    let nonce = match options.nonce {
+        Some(nonce) => U256::from(nonce),
+        None => nonces.next(web3, chain_id, from).await?,
+    };
+    tx.nonce = Some(nonce);
 
-    let wei = unit::to_wei(eth.to_string().as_str(), "ether");
-    let wei: u128 = wei.parse().unwrap();
-    let wei = U256::from(wei);
-    println!("send wei: {}", &wei);
+    let result = match signer.sign_transaction(web3, tx).await {
+        Ok(raw) => web3
+            .eth()
+            .send_raw_transaction(raw)
+            .await
+            .map_err(Error::from),
+        Err(e) => Err(e),
+    };
+    if options.nonce.is_none() {
+        match &result {
+            Ok(_) => {}
+            Err(Error::NonceConflict(_)) => nonces.reset(chain_id, from),
+            Err(_) => nonces.release(chain_id, from, nonce),
+        }
+    }
+
+    result.map(|hash| (hash, gas))
+}
+
+/// Like [`submit_transaction`], returning a handle that decodes the receipt against `abi`.
+async fn send_transaction(
+    web3: &Web3<Http>,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    tx: TransactionParameters,
+    abi: &ethabi::Contract,
+    options: &TxOptions,
+) -> Web3Result<PendingTx> {
+    let (hash, gas) = submit_transaction(web3, network, signer, tx, options)
+        .await
+        .map_err(|e| e.with_abi(abi))?;
+    Ok(PendingTx::new(
+        web3.clone(),
+        hash,
+        abi.clone(),
+        options.wait_policy(network),
+    )
+    .with_gas(gas))
+}
 
+/// Broadcasts `call` on `contract` without waiting for it to be mined.
+async fn submit_call(
+    web3: &Web3<Http>,
+    contract: &Contract<Http>,
+    call: impl ContractCall,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    options: &TxOptions,
+) -> Web3Result<PendingTx> {
+    let (_, data) = bindings::encode(contract.abi(), call)?;
     let tx = TransactionParameters {
-        to: Some(to),
-        value: wei,
-        gas: U256::from(GAS_LIMIT),
-        gas_price: Some(U256::from(GAS_PRICE)),
+        to: Some(contract.address()),
+        data: Bytes(data),
         ..Default::default()
     };
 
-    let signed = cli
-        .accounts()
-        .sign_transaction(tx, SecretKeyRef::from(&prev_key))
-        .await?;
-    let result = cli
-        .eth()
-        .send_raw_transaction(signed.raw_transaction)
+    send_transaction(web3, network, signer, tx, contract.abi(), options).await
+}
+
+/// Deploys `bytecode`, appending `params` encoded for the constructor in `abi`.
+async fn deploy_contract(
+    signer: &dyn Signer,
+    abi: &[u8],
+    network: &NetworkConfig,
+    bytecode: &str,
+    params: impl Tokenize,
+    options: &TxOptions,
+) -> Web3Result<TxOutcome> {
+    let outcome = submit_deploy_contract(signer, abi, network, bytecode, params, options)
+        .await?
+        .settle()
         .await?;
+    if outcome.is_pending() {
+        return Ok(outcome);
+    }
+    if !outcome.succeeded() || outcome.contract_address.is_none() {
+        return Err(Error::Failed(outcome.transaction_hash));
+    }
 
-    println!("sendEth: {:?}", result);
+    Ok(outcome)
+}
 
-    Ok(())
+/// Broadcasts the creation of a contract without waiting for it. Its address is in the
+/// receipt once mined.
+async fn submit_deploy_contract(
+    signer: &dyn Signer,
+    abi: &[u8],
+    network: &NetworkConfig,
+    bytecode: &str,
+    params: impl Tokenize,
+    options: &TxOptions,
+) -> Web3Result<PendingTx> {
+    let abi = web3::ethabi::Contract::load(abi)?;
+    let code = hex::decode(bytecode.trim_start_matches("0x"))
+        .map_err(|e| Error::Decode(format!("invalid bytecode: {}", e)))?;
+    let params = params.into_tokens();
+    let data = match abi.constructor() {
+        Some(constructor) => constructor.encode_input(code, &params)?,
+        None if params.is_empty() => code,
+        None => {
+            return Err(Error::Abi(web3::ethabi::Error::Other(
+                "constructor arguments given, but the abi has no constructor".into(),
+            )))
+        }
+    };
+    let tx = TransactionParameters {
+        to: None,
+        data: Bytes(data),
+        ..Default::default()
+    };
+
+    let web3 = connect(network)?;
+    send_transaction(&web3, network, signer, tx, &abi, options).await
+}
+
+/// keccak256 of hex encoded `bytecode`.
+fn bytecode_hash(bytecode: &str) -> H256 {
+    let code = hex::decode(bytecode.trim_start_matches("0x")).unwrap_or_default();
+    H256(web3::signing::keccak256(&code))
+}
+
+pub fn parse_address(address: String) -> Option<Address> {
+    address.trim_start_matches("0x").parse().ok()
+}
+
+/// Decodes hex encoded bytes, with or without `0x`.
+pub fn parse_bytes(data: &str) -> Option<Vec<u8>> {
+    hex::decode(data.trim_start_matches("0x")).ok()
+}
+
+/// Parses a decimal `uint256`, or a hex one with `0x`.
+pub fn parse_u256(value: &str) -> Option<U256> {
+    match value.strip_prefix("0x") {
+        Some(hex) if !hex.is_empty() => U256::from_str_radix(hex, 16).ok(),
+        // both would read an empty string as 0
+        None if !value.is_empty() => U256::from_dec_str(value).ok(),
+        _ => None,
+    }
+}
+
+pub fn parse_hash(hash: &str) -> Option<H256> {
+    hash.trim_start_matches("0x").parse().ok()
+}
+
+/// Native currency balance of `address`, in the smallest unit.
+pub async fn get_balance(network: &NetworkConfig, address: Address) -> Web3Result<U256> {
+    let cli = connect(network)?;
+
+    Ok(cli.eth().balance(address, None).await?)
+}
+
+/// Sends `wei` of the native currency to `to`, waiting as configured.
+pub async fn send_eth(
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    to: Address,
+    wei: U256,
+    options: &TxOptions,
+) -> Web3Result<TxOutcome> {
+    submit_send_eth(network, signer, to, wei, options)
+        .await?
+        .settle()
+        .await
+}
+
+/// Broadcasts a transfer of `wei` to `to` without waiting for it; see [`PendingTx`].
+pub async fn submit_send_eth(
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    to: Address,
+    wei: U256,
+    options: &TxOptions,
+) -> Web3Result<PendingTx> {
+    let cli = connect(network)?;
+    let tx = TransactionParameters {
+        to: Some(to),
+        value: wei,
+        ..Default::default()
+    };
+
+    send_transaction(
+        &cli,
+        network,
+        signer,
+        tx,
+        &ethabi::Contract::default(),
+        options,
+    )
+    .await
 }
 
 pub type Web3Result<T> = Result<T, Error>;
 
-#[derive(thiserror::Error, Debug, PartialOrd, PartialEq, Clone)]
+#[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error("rpc error: {0}")]
+    Rpc(#[source] web3::Error),
+    #[error("rpc timeout: {0}")]
+    Timeout(#[source] web3::Error),
+    #[error("execution reverted: {0}")]
+    Revert(Revert),
+    #[error("transaction failed: {0:?}")]
+    Failed(H256),
+    #[error("transaction {0:?} was not confirmed in time")]
+    NotConfirmed(H256),
+    #[error("insufficient balance of token {token_id}: holds {balance}, needs {amount}")]
+    InsufficientBalance {
+        token_id: U256,
+        balance: U256,
+        amount: U256,
+    },
+    #[error("insufficient funds: {0}")]
+    InsufficientFunds(#[source] web3::Error),
+    #[error("nonce conflict: {0}")]
+    NonceConflict(#[source] web3::Error),
+    #[error("abi error: {0}")]
+    Abi(#[from] web3::ethabi::Error),
+    #[error("decode error: {0}")]
+    Decode(String),
+    #[error("signing error: {0}")]
+    Signing(String),
+    #[error("{address:?} is not an {expected} contract")]
+    UnsupportedInterface { address: Address, expected: Schema },
+    #[error("signer address {signer:?} does not match expected {expected:?}")]
+    AddressMismatch { expected: Address, signer: Address },
+    #[error("config error: {0}")]
+    Config(#[from] prelude::config::Error),
     #[error("internal error: {0}")]
     Internal(String),
 }
 
-impl From<web3::Error> for Error {
-    fn from(e: web3::Error) -> Self {
-        let msg = format!("rust_web3 error: {:?}", e);
-        Self::Internal(msg)
+impl Error {
+    /// Decodes custom errors in a revert against the ABI of the contract that was called.
+    pub fn with_abi(self, abi: &web3::ethabi::Contract) -> Self {
+        match self {
+            Error::Revert(revert) => Error::Revert(revert.resolve(abi)),
+            e => e,
+        }
     }
 }
 
-impl From<web3::ethabi::Error> for Error {
-    fn from(e: web3::ethabi::Error) -> Self {
-        let msg = format!("rust_web3 abi error: {:?}", e);
-        Self::Internal(msg)
+impl From<web3::Error> for Error {
+    fn from(e: web3::Error) -> Self {
+        match &e {
+            web3::Error::Rpc(rpc) => {
+                let data = rpc.data.as_ref().and_then(|d| d.as_str());
+                if let Some(revert) = Revert::from_rpc(rpc.code.code(), &rpc.message, data) {
+                    return Self::Revert(revert);
+                }
+                let message = rpc.message.to_lowercase();
+                if message.contains("insufficient funds") {
+                    return Self::InsufficientFunds(e);
+                }
+                if message.contains("nonce too low")
+                    || message.contains("nonce too high")
+                    || message.contains("already known")
+                    || message.contains("replacement transaction underpriced")
+                {
+                    return Self::NonceConflict(e);
+                }
+                Self::Rpc(e)
+            }
+            web3::Error::Transport(TransportError::Message(message))
+                if message.contains("timed out") || message.contains("timeout") =>
+            {
+                Self::Timeout(e)
+            }
+            web3::Error::Recovery(recovery) => Self::Signing(recovery.to_string()),
+            _ => Self::Rpc(e),
+        }
     }
 }
 
 impl From<web3::contract::Error> for Error {
     fn from(e: web3::contract::Error) -> Self {
-        let msg = format!("rust_web3 contract error: {:?}", e);
-        Self::Internal(msg)
+        match e {
+            web3::contract::Error::Api(e) => Self::from(e),
+            web3::contract::Error::Abi(e) => Self::Abi(e),
+            web3::contract::Error::Deployment(e) => Self::from(e),
+            web3::contract::Error::InvalidOutputType(msg) => Self::Decode(msg),
+            web3::contract::Error::InterfaceUnsupported => {
+                Self::Decode("interface unsupported".to_string())
+            }
+        }
     }
 }
 
 impl From<web3::contract::deploy::Error> for Error {
     fn from(e: web3::contract::deploy::Error) -> Self {
-        let msg = format!("rust_web3 contract deploy error: {:?}", e);
-        Self::Internal(msg)
+        match e {
+            web3::contract::deploy::Error::Api(e) => Self::from(e),
+            web3::contract::deploy::Error::Abi(e) => Self::Abi(e),
+            web3::contract::deploy::Error::ContractDeploymentFailure(hash) => Self::Failed(hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_and_hex_u256() {
+        assert_eq!(parse_u256("0"), Some(U256::zero()));
+        assert_eq!(parse_u256("1000"), Some(U256::from(1000)));
+        assert_eq!(parse_u256("0x3e8"), Some(U256::from(1000)));
+        assert_eq!(parse_u256("0xFF"), Some(U256::from(255)));
+        assert_eq!(
+            parse_u256(
+                "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+            ),
+            Some(U256::MAX)
+        );
+        assert_eq!(
+            parse_u256(&format!("0x{}", "f".repeat(64))),
+            Some(U256::MAX)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_u256() {
+        for value in [
+            "",
+            "0x",
+            "12x",
+            "0xzz",
+            "-1",
+            "1.5",
+            " 1",
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936",
+        ] {
+            assert_eq!(parse_u256(value), None, "{:?}", value);
+        }
+        assert_eq!(parse_u256(&format!("0x1{}", "0".repeat(64))), None);
     }
 }
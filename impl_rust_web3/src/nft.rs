@@ -0,0 +1,424 @@
+use crate::batch::{BatchMode, ReadBatch};
+use crate::bindings::{query, ContractCall};
+use crate::nft_1155::client::Erc1155;
+use crate::nft_721::client::Erc721;
+use crate::signer::Signer;
+use crate::{
+    connect, contract, deploy_contract, ensure_interface, parse_address, submit_call,
+    submit_deploy_contract, ContractInfo, Error, MintOutcome, PendingTx, TxOptions, TxOutcome,
+    Web3Result,
+};
+use async_trait::async_trait;
+use prelude::config::{ConfigResult, NetworkConfig};
+use prelude::Schema;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use web3::contract::Contract;
+use web3::transports::Http;
+use web3::types::{Address, H256, U256};
+use web3::Web3;
+
+/// What the ERC721 and ERC1155 clients have in common, so callers can work with either
+/// standard chosen at runtime by [`Schema`]. Amounts are always 1 for ERC721.
+#[async_trait]
+pub trait NftClient: fmt::Debug + Send + Sync {
+    fn schema(&self) -> Schema;
+
+    fn contract_address(&self) -> Address;
+
+    /// Reads name and token counters in one round trip.
+    async fn info(&self, mode: BatchMode) -> Web3Result<ContractInfo>;
+
+    async fn name(&self) -> Web3Result<String>;
+
+    async fn symbol(&self) -> Web3Result<String>;
+
+    /// ERC721 `tokenURI` / ERC1155 `uri`.
+    async fn token_uri(&self, token_id: U256) -> Web3Result<String>;
+
+    /// The account allowed to mint, i.e. the `Ownable` owner.
+    async fn owner(&self) -> Web3Result<Address>;
+
+    /// How many of `token_id` `account` holds; 0 or 1 for ERC721.
+    async fn balance_of(&self, account: Address, token_id: U256) -> Web3Result<U256>;
+
+    async fn mint(
+        &self,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<MintOutcome>;
+
+    /// Broadcasts a mint without waiting for it; see [`PendingTx`].
+    async fn submit_mint(
+        &self,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx>;
+
+    /// Sends `amount` of `token_id` to `to`, passing `data` to the receiver hook.
+    async fn transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<TxOutcome>;
+
+    /// Broadcasts a transfer without waiting for it; see [`PendingTx`].
+    async fn submit_transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx>;
+}
+
+/// One NFT standard: the contract artifacts, the generated bindings of the calls both
+/// standards share, and the [`NftClient`] operations that differ between them.
+#[async_trait]
+pub trait Standard: Clone + fmt::Debug + Send + Sync + 'static {
+    const SCHEMA: Schema;
+    const ABI: &'static [u8];
+    /// Hex encoded creation code.
+    const BIN: &'static str;
+
+    type Name: ContractCall<Output = String> + Default + Send;
+    type Symbol: ContractCall<Output = String> + Default + Send;
+    type Owner: ContractCall<Output = Address> + Default + Send;
+    type LatestTokenId: ContractCall<Output = U256> + Default + Send;
+    type TotalSupply: ContractCall<Output = U256> + Default + Send;
+    type TotalOwned: ContractCall<Output = U256> + Default + Send;
+    type SupportsInterface: ContractCall<Output = bool> + Send;
+
+    /// The configured address of the contract.
+    fn address(network: &NetworkConfig) -> ConfigResult<String>;
+
+    /// The ERC-165 query for the interface id of the standard.
+    fn supports_interface() -> Self::SupportsInterface;
+
+    async fn token_uri(client: &Client<Self>, token_id: U256) -> Web3Result<String>;
+
+    async fn balance_of(
+        client: &Client<Self>,
+        account: Address,
+        token_id: U256,
+    ) -> Web3Result<U256>;
+
+    async fn submit_mint(
+        client: &Client<Self>,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx>;
+
+    async fn submit_transfer(
+        client: &Client<Self>,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx>;
+}
+
+/// A contract of standard `S`, with the reads both standards share. The calls specific
+/// to one standard are implemented on [`crate::nft_721::client::Client`] and
+/// [`crate::nft_1155::client::Client`].
+#[derive(Clone, Debug)]
+pub struct Client<S> {
+    wallet_address: Address,
+    signer: Arc<dyn Signer>,
+    contract_address: Address,
+    network: NetworkConfig,
+    web3: Web3<Http>,
+    contract: Contract<Http>,
+    standard: PhantomData<S>,
+}
+
+impl<S: Standard> Client<S> {
+    /// Connects to the configured contract and checks via ERC-165 that it implements `S`.
+    pub async fn new(network: NetworkConfig, signer: Arc<dyn Signer>) -> Web3Result<Self> {
+        let address = S::address(&network)?;
+        let contract_address = parse_address(address.clone())
+            .ok_or_else(|| Error::Internal(format!("invalid contract address: {}", address)))?;
+        let web3 = connect(&network)?;
+        let contract = contract(&web3, contract_address, S::ABI)?;
+        ensure_interface(&web3, &contract, S::supports_interface(), S::SCHEMA).await?;
+
+        Ok(Client {
+            wallet_address: signer.address(),
+            signer,
+            contract_address,
+            network,
+            web3,
+            contract,
+            standard: PhantomData,
+        })
+    }
+
+    pub fn web3(&self) -> &Web3<Http> {
+        &self.web3
+    }
+
+    pub fn contract(&self) -> &Contract<Http> {
+        &self.contract
+    }
+
+    pub fn contract_address(&self) -> Address {
+        self.contract_address
+    }
+
+    /// The signer's address, which transfers send from.
+    pub fn wallet_address(&self) -> Address {
+        self.wallet_address
+    }
+
+    /// Reads name and token counters in one round trip.
+    pub async fn info(&self, mode: BatchMode) -> Web3Result<ContractInfo> {
+        let mut batch = ReadBatch::new();
+        let name = batch.add(&self.contract, S::Name::default())?;
+        let latest_token_id = batch.add(&self.contract, S::LatestTokenId::default())?;
+        let total_supply = batch.add(&self.contract, S::TotalSupply::default())?;
+        let total_owned = batch.add(&self.contract, S::TotalOwned::default())?;
+        let mut results = batch.execute(&self.web3, mode).await?;
+
+        Ok(ContractInfo {
+            name: results.take(name)?,
+            latest_token_id: results.take(latest_token_id)?,
+            total_supply: results.take(total_supply)?,
+            total_owned: results.take(total_owned)?,
+        })
+    }
+
+    pub async fn name(&self) -> Web3Result<String> {
+        self.query(S::Name::default()).await
+    }
+
+    pub async fn latest_token_id(&self) -> Web3Result<U256> {
+        self.query(S::LatestTokenId::default()).await
+    }
+
+    pub async fn total_supply(&self) -> Web3Result<U256> {
+        self.query(S::TotalSupply::default()).await
+    }
+
+    pub async fn total_owned(&self) -> Web3Result<U256> {
+        self.query(S::TotalOwned::default()).await
+    }
+
+    pub async fn symbol(&self) -> Web3Result<String> {
+        self.query(S::Symbol::default()).await
+    }
+
+    /// The account allowed to mint, i.e. the `Ownable` owner.
+    pub async fn owner(&self) -> Web3Result<Address> {
+        self.query(S::Owner::default()).await
+    }
+
+    pub(crate) async fn query<C: ContractCall>(&self, call: C) -> Web3Result<C::Output> {
+        query(&self.web3, &self.contract, call).await
+    }
+
+    /// Signs `call` and broadcasts it without waiting; see [`PendingTx`].
+    pub(crate) async fn submit(
+        &self,
+        call: impl ContractCall,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        submit_call(
+            &self.web3,
+            &self.contract,
+            call,
+            &self.network,
+            self.signer.as_ref(),
+            options,
+        )
+        .await
+    }
+
+    /// Identifies the contract version [`Client::deploy`] creates.
+    pub fn bytecode_hash() -> H256 {
+        crate::bytecode_hash(S::BIN.trim())
+    }
+
+    /// Deploys a new contract; its address is in the returned outcome.
+    pub async fn deploy(
+        network: &NetworkConfig,
+        signer: &dyn Signer,
+        name: String,
+        symbol: String,
+        options: &TxOptions,
+    ) -> Web3Result<TxOutcome> {
+        deploy_contract(
+            signer,
+            S::ABI,
+            network,
+            S::BIN.trim(),
+            (name, symbol),
+            options,
+        )
+        .await
+    }
+
+    /// Broadcasts the deployment without waiting for it; the address is in the receipt.
+    pub async fn submit_deploy(
+        network: &NetworkConfig,
+        signer: &dyn Signer,
+        name: String,
+        symbol: String,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        submit_deploy_contract(
+            signer,
+            S::ABI,
+            network,
+            S::BIN.trim(),
+            (name, symbol),
+            options,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<S: Standard> NftClient for Client<S> {
+    fn schema(&self) -> Schema {
+        S::SCHEMA
+    }
+
+    fn contract_address(&self) -> Address {
+        self.contract_address
+    }
+
+    async fn info(&self, mode: BatchMode) -> Web3Result<ContractInfo> {
+        Client::info(self, mode).await
+    }
+
+    async fn name(&self) -> Web3Result<String> {
+        Client::name(self).await
+    }
+
+    async fn symbol(&self) -> Web3Result<String> {
+        Client::symbol(self).await
+    }
+
+    async fn token_uri(&self, token_id: U256) -> Web3Result<String> {
+        S::token_uri(self, token_id).await
+    }
+
+    async fn owner(&self) -> Web3Result<Address> {
+        Client::owner(self).await
+    }
+
+    async fn balance_of(&self, account: Address, token_id: U256) -> Web3Result<U256> {
+        S::balance_of(self, account, token_id).await
+    }
+
+    async fn mint(
+        &self,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<MintOutcome> {
+        let tx = S::submit_mint(self, content_hash, amount, options)
+            .await?
+            .settle()
+            .await?;
+
+        MintOutcome::new(tx, self.contract_address)
+    }
+
+    async fn submit_mint(
+        &self,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        S::submit_mint(self, content_hash, amount, options).await
+    }
+
+    async fn transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<TxOutcome> {
+        S::submit_transfer(self, to, token_id, amount, data, options)
+            .await?
+            .settle()
+            .await
+    }
+
+    async fn submit_transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        S::submit_transfer(self, to, token_id, amount, data, options).await
+    }
+}
+
+/// Connects to the configured contract of `schema` on `network`.
+pub async fn client(
+    schema: Schema,
+    network: NetworkConfig,
+    signer: Arc<dyn Signer>,
+) -> Web3Result<Box<dyn NftClient>> {
+    Ok(match schema {
+        Schema::ERC721 => Box::new(Client::<Erc721>::new(network, signer).await?),
+        Schema::ERC1155 => Box::new(Client::<Erc1155>::new(network, signer).await?),
+    })
+}
+
+/// Deploys a new contract of `schema`; its address is in the returned outcome.
+pub async fn deploy(
+    schema: Schema,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    name: String,
+    symbol: String,
+    options: &TxOptions,
+) -> Web3Result<TxOutcome> {
+    match schema {
+        Schema::ERC721 => Client::<Erc721>::deploy(network, signer, name, symbol, options).await,
+        Schema::ERC1155 => Client::<Erc1155>::deploy(network, signer, name, symbol, options).await,
+    }
+}
+
+/// Like [`deploy`], but returns once the deployment is broadcast.
+pub async fn submit_deploy(
+    schema: Schema,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    name: String,
+    symbol: String,
+    options: &TxOptions,
+) -> Web3Result<PendingTx> {
+    match schema {
+        Schema::ERC721 => {
+            Client::<Erc721>::submit_deploy(network, signer, name, symbol, options).await
+        }
+        Schema::ERC1155 => {
+            Client::<Erc1155>::submit_deploy(network, signer, name, symbol, options).await
+        }
+    }
+}
+
+/// Identifies the contract version [`deploy`] creates for `schema`.
+pub fn bytecode_hash(schema: Schema) -> H256 {
+    match schema {
+        Schema::ERC721 => Client::<Erc721>::bytecode_hash(),
+        Schema::ERC1155 => Client::<Erc1155>::bytecode_hash(),
+    }
+}
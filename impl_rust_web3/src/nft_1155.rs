@@ -1 +1,5 @@
+/// Typed bindings `build.rs` generates from `abi.json`.
+pub mod bindings {
+    include!(concat!(env!("OUT_DIR"), "/nft_1155.rs"));
+}
 pub mod client;
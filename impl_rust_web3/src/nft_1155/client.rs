@@ -1,153 +1,237 @@
-use crate::{contract, deploy_contract, parse_address, Web3Result};
-use prelude::*;
-use secp256k1::SecretKey;
-use std::env;
-use std::str::FromStr;
-use web3::contract::Options;
-use web3::signing::SecretKeyRef;
+use crate::nft::{self, Standard};
+use crate::nft_1155::bindings::functions::{
+    BalanceOf, BalanceOfBatch, LatestTokenId, Mint, Name, Owner, SafeBatchTransferFrom,
+    SafeTransferFrom, SupportsInterface, Symbol, TotalOwned, TotalSupply, Uri,
+};
+use crate::{Error, MintOutcome, PendingTx, TxOptions, TxOutcome, Web3Result};
+use async_trait::async_trait;
+use prelude::config::{ConfigResult, NetworkConfig};
+use prelude::Schema;
 use web3::types::{Address, Bytes, U256};
 
+/// ERC-165 interface id of ERC1155.
+const INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+/// The ERC1155 contract in `abi.json` / `bin`.
 #[derive(Clone, Debug)]
-pub struct Client {
-    wallet_address: Address,
-    wallet_secret: String,
-    contract_address: Address,
-    network: Network,
-}
+pub struct Erc1155;
+
+pub type Client = nft::Client<Erc1155>;
 
 impl Client {
-    #[allow(unused)]
-    pub fn new(network: Network) -> Self {
-        let wallet_address = env::var("WALLET_ADDRESS").expect("WALLET_ADDRESS must be set");
-        let wallet_secret = env::var("WALLET_SECRET").expect("WALLET_SECRET must be set");
-
-        Client {
-            wallet_address: parse_address(wallet_address).unwrap(),
-            wallet_secret,
-            contract_address: parse_address(network.nft_721_address().to_owned()).unwrap(),
-            network,
-        }
+    pub async fn uri(&self, token_id: U256) -> Web3Result<String> {
+        self.query(Uri { token_id }).await
     }
 
-    pub async fn name(&self) -> Web3Result<String> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("name", (), None, Options::default(), None);
-        let result: String = result.await?;
-
-        Ok(result)
-    }
-
-    pub async fn latest_token_id(&self) -> Web3Result<u128> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("latestTokenId", (), None, Options::default(), None);
-        let result: u128 = result.await?;
-
-        Ok(result)
-    }
-
-    pub async fn total_supply(&self) -> Web3Result<u128> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("totalSupply", (), None, Options::default(), None);
-        let result: u128 = result.await?;
-
-        Ok(result)
-    }
-
-    pub async fn total_owned(&self) -> Web3Result<u128> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("totalOwned", (), None, Options::default(), None);
-        let result: u128 = result.await?;
-
-        Ok(result)
-    }
-
-    pub async fn mint(&self, hash: String, amount: u128) -> Web3Result<()> {
-        let secret_key = SecretKey::from_str(&self.wallet_secret).unwrap();
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract
-            .signed_call_with_confirmations(
-                "mint",
-                (hash, amount),
-                Options::with(|opt| {
-                    opt.gas = Some(U256::from(GAS_LIMIT));
-                    opt.gas_price = Some(U256::from(GAS_PRICE));
-                }),
-                1,
-                SecretKeyRef::from(&secret_key),
-            )
+    pub async fn mint(
+        &self,
+        hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<MintOutcome> {
+        let tx = self
+            .submit_mint(hash, amount, options)
+            .await?
+            .settle()
             .await?;
 
-        println!("tx id: {:?}", result.transaction_hash);
-        println!("gas used: {:?}", result.gas_used.unwrap_or_default());
-        println!("status: {:?}", result.status.unwrap_or_default());
+        MintOutcome::new(tx, self.contract_address())
+    }
 
-        Ok(())
+    /// Broadcasts a mint without waiting for it; see [`PendingTx`].
+    pub async fn submit_mint(
+        &self,
+        hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        let mint = Mint {
+            content_hash: hash,
+            amount,
+        };
+        self.submit(mint, options).await
     }
 
-    pub async fn transfer(&self, to: Address, token_id: u128) -> Web3Result<()> {
-        let secret_key = SecretKey::from_str(&self.wallet_secret).unwrap();
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract
-            .signed_call_with_confirmations(
-                "safeTransferFrom",
-                (
-                    self.wallet_address,
-                    to,
-                    token_id,
-                    1 as u64,
-                    Bytes::default(),
-                ),
-                Options::with(|opt| {
-                    opt.gas = Some(U256::from(GAS_LIMIT));
-                    opt.gas_price = Some(U256::from(GAS_PRICE));
-                }),
-                1,
-                SecretKeyRef::from(&secret_key),
-            )
-            .await?;
+    /// Sends `amount` of `token_id` to `to`, passing `data` to the receiver hook.
+    pub async fn transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<TxOutcome> {
+        self.submit_transfer(to, token_id, amount, data, options)
+            .await?
+            .settle()
+            .await
+    }
 
-        println!("tx id: {:?}", result.transaction_hash);
-        println!("gas used: {:?}", result.gas_used.unwrap_or_default());
-        println!("status: {:?}", result.status.unwrap_or_default());
+    /// Broadcasts a transfer without waiting for it; see [`PendingTx`].
+    pub async fn submit_transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        self.ensure_balance(vec![token_id], vec![amount]).await?;
+
+        let transfer = SafeTransferFrom {
+            from: self.wallet_address(),
+            to,
+            id: token_id,
+            amount,
+            data: Bytes(data),
+        };
+        self.submit(transfer, options).await
+    }
 
-        Ok(())
+    /// Sends `amounts[i]` of token `ids[i]` to `to`, all in one transaction.
+    pub async fn transfer_batch(
+        &self,
+        to: Address,
+        ids: Vec<U256>,
+        amounts: Vec<U256>,
+        options: &TxOptions,
+    ) -> Web3Result<TxOutcome> {
+        self.submit_transfer_batch(to, ids, amounts, options)
+            .await?
+            .settle()
+            .await
     }
 
-    pub async fn deploy(&self) -> Web3Result<()> {
-        let contract = deploy_contract(
-            self.wallet_secret.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-            include_str!("bin").trim(),
-        )
-        .await?;
+    /// Broadcasts a batch transfer without waiting for it; see [`PendingTx`].
+    pub async fn submit_transfer_batch(
+        &self,
+        to: Address,
+        ids: Vec<U256>,
+        amounts: Vec<U256>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        if ids.is_empty() || ids.len() != amounts.len() {
+            return Err(Error::Internal(format!(
+                "batch transfer needs as many amounts as token ids, got {} ids and {} amounts",
+                ids.len(),
+                amounts.len()
+            )));
+        }
+        self.ensure_balance(ids.clone(), amounts.clone()).await?;
+
+        let transfer_batch = SafeBatchTransferFrom {
+            from: self.wallet_address(),
+            to,
+            ids,
+            amounts,
+            data: Bytes::default(),
+        };
+        self.submit(transfer_batch, options).await
+    }
 
-        println!("deployed to: {:?}", contract.address());
+    /// Balance of `accounts[i]` in token `ids[i]`, for every `i`.
+    pub async fn balance_of_batch(
+        &self,
+        accounts: Vec<Address>,
+        ids: Vec<U256>,
+    ) -> Web3Result<Vec<U256>> {
+        if accounts.len() != ids.len() {
+            return Err(Error::Internal(format!(
+                "balance query needs as many accounts as token ids, got {} accounts and {} ids",
+                accounts.len(),
+                ids.len()
+            )));
+        }
+
+        self.query(BalanceOfBatch { accounts, ids }).await
+    }
+
+    pub async fn balance_of(&self, account: Address, token_id: U256) -> Web3Result<U256> {
+        let balance_of = BalanceOf {
+            account,
+            id: token_id,
+        };
+        self.query(balance_of).await
+    }
 
+    /// Fails with [`Error::InsufficientBalance`] unless the wallet holds `amounts[i]` of
+    /// every `ids[i]`, so a transfer that would revert is not sent.
+    async fn ensure_balance(&self, ids: Vec<U256>, amounts: Vec<U256>) -> Web3Result<()> {
+        let accounts = vec![self.wallet_address(); ids.len()];
+        let balances = self.balance_of_batch(accounts, ids.clone()).await?;
+
+        // the same id may appear more than once in a batch: (id, balance, total amount)
+        let mut required: Vec<(U256, U256, U256)> = vec![];
+        for ((id, amount), balance) in ids.into_iter().zip(amounts).zip(balances) {
+            match required.iter_mut().find(|(i, _, _)| *i == id) {
+                Some((_, _, total)) => *total = total.saturating_add(amount),
+                None => required.push((id, balance, amount)),
+            }
+        }
+        for (token_id, balance, amount) in required {
+            if balance < amount {
+                return Err(Error::InsufficientBalance {
+                    token_id,
+                    balance,
+                    amount,
+                });
+            }
+        }
         Ok(())
     }
 }
+
+#[async_trait]
+impl Standard for Erc1155 {
+    const SCHEMA: Schema = Schema::ERC1155;
+    const ABI: &'static [u8] = include_bytes!("abi.json");
+    const BIN: &'static str = include_str!("bin");
+
+    type Name = Name;
+    type Symbol = Symbol;
+    type Owner = Owner;
+    type LatestTokenId = LatestTokenId;
+    type TotalSupply = TotalSupply;
+    type TotalOwned = TotalOwned;
+    type SupportsInterface = SupportsInterface;
+
+    fn address(network: &NetworkConfig) -> ConfigResult<String> {
+        network.nft_1155_address()
+    }
+
+    fn supports_interface() -> SupportsInterface {
+        SupportsInterface {
+            interface_id: INTERFACE_ID,
+        }
+    }
+
+    async fn token_uri(client: &Client, token_id: U256) -> Web3Result<String> {
+        client.uri(token_id).await
+    }
+
+    async fn balance_of(client: &Client, account: Address, token_id: U256) -> Web3Result<U256> {
+        client.balance_of(account, token_id).await
+    }
+
+    async fn submit_mint(
+        client: &Client,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        client.submit_mint(content_hash, amount, options).await
+    }
+
+    async fn submit_transfer(
+        client: &Client,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        client
+            .submit_transfer(to, token_id, amount, data, options)
+            .await
+    }
+}
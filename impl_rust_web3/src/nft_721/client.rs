@@ -1,146 +1,138 @@
-use crate::{contract, deploy_contract, parse_address, Web3Result};
-use prelude::*;
-use secp256k1::SecretKey;
-use std::env;
-use std::str::FromStr;
-use web3::contract::Options;
-use web3::signing::SecretKeyRef;
-use web3::types::{Address, U256};
-
+use crate::nft::{self, Standard};
+use crate::nft_721::bindings::functions::{
+    IsOwner, LatestTokenId, Mint, Name, Owner, SafeTransferFromWithData, SupportsInterface, Symbol,
+    TokenUri, TotalOwned, TotalSupply,
+};
+use crate::{Error, MintOutcome, PendingTx, TxOptions, TxOutcome, Web3Result};
+use async_trait::async_trait;
+use prelude::config::{ConfigResult, NetworkConfig};
+use prelude::Schema;
+use web3::types::{Address, Bytes, U256};
+
+/// ERC-165 interface id of ERC721.
+const INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+
+/// The ERC721 contract in `abi.json` / `bin`.
 #[derive(Clone, Debug)]
-pub struct Client {
-    wallet_address: Address,
-    wallet_secret: String,
-    contract_address: Address,
-    network: Network,
-}
+pub struct Erc721;
+
+pub type Client = nft::Client<Erc721>;
 
 impl Client {
-    pub fn new(network: Network) -> Self {
-        let wallet_address = env::var("WALLET_ADDRESS").expect("WALLET_ADDRESS must be set");
-        let wallet_secret = env::var("WALLET_SECRET").expect("WALLET_SECRET must be set");
-
-        Client {
-            wallet_address: parse_address(wallet_address).unwrap(),
-            wallet_secret,
-            contract_address: parse_address(network.nft_721_address().to_owned()).unwrap(),
-            network,
-        }
+    pub async fn token_uri(&self, token_id: U256) -> Web3Result<String> {
+        self.query(TokenUri { token_id }).await
     }
 
-    pub async fn name(&self) -> Web3Result<String> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("name", (), None, Options::default(), None);
-        let result: String = result.await?;
-
-        Ok(result)
+    pub async fn is_owner(&self, token_id: U256, target: Address) -> Web3Result<bool> {
+        self.query(IsOwner { token_id, target }).await
     }
 
-    pub async fn latest_token_id(&self) -> Web3Result<u128> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("latestTokenId", (), None, Options::default(), None);
-        let result: u128 = result.await?;
+    pub async fn mint(&self, hash: String, options: &TxOptions) -> Web3Result<MintOutcome> {
+        let tx = self.submit_mint(hash, options).await?.settle().await?;
+
+        MintOutcome::new(tx, self.contract_address())
+    }
 
-        Ok(result)
+    /// Broadcasts a mint without waiting for it; see [`PendingTx`].
+    pub async fn submit_mint(&self, hash: String, options: &TxOptions) -> Web3Result<PendingTx> {
+        self.submit(Mint { content_hash: hash }, options).await
     }
 
-    pub async fn total_supply(&self) -> Web3Result<u128> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("totalSupply", (), None, Options::default(), None);
-        let result: u128 = result.await?;
+    /// Sends `token_id` to `to`, passing `data` to the receiver hook.
+    pub async fn transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<TxOutcome> {
+        self.submit_transfer(to, token_id, data, options)
+            .await?
+            .settle()
+            .await
+    }
 
-        Ok(result)
+    /// Broadcasts a transfer without waiting for it; see [`PendingTx`].
+    pub async fn submit_transfer(
+        &self,
+        to: Address,
+        token_id: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        let transfer = SafeTransferFromWithData {
+            from: self.wallet_address(),
+            to,
+            token_id,
+            data: Bytes(data),
+        };
+        self.submit(transfer, options).await
     }
+}
 
-    pub async fn total_owned(&self) -> Web3Result<u128> {
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract.query("totalOwned", (), None, Options::default(), None);
-        let result: u128 = result.await?;
+#[async_trait]
+impl Standard for Erc721 {
+    const SCHEMA: Schema = Schema::ERC721;
+    const ABI: &'static [u8] = include_bytes!("abi.json");
+    const BIN: &'static str = include_str!("bin");
+
+    type Name = Name;
+    type Symbol = Symbol;
+    type Owner = Owner;
+    type LatestTokenId = LatestTokenId;
+    type TotalSupply = TotalSupply;
+    type TotalOwned = TotalOwned;
+    type SupportsInterface = SupportsInterface;
+
+    fn address(network: &NetworkConfig) -> ConfigResult<String> {
+        network.nft_721_address()
+    }
 
-        Ok(result)
+    fn supports_interface() -> SupportsInterface {
+        SupportsInterface {
+            interface_id: INTERFACE_ID,
+        }
     }
 
-    pub async fn mint(&self, hash: String) -> Web3Result<()> {
-        let secret_key = SecretKey::from_str(&self.wallet_secret).unwrap();
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract
-            .signed_call_with_confirmations(
-                "mint",
-                hash,
-                Options::with(|opt| {
-                    opt.gas = Some(U256::from(GAS_LIMIT));
-                    opt.gas_price = Some(U256::from(GAS_PRICE));
-                }),
-                1,
-                SecretKeyRef::from(&secret_key),
-            )
-            .await?;
-
-        println!("tx id: {:?}", result.transaction_hash);
-        println!("gas used: {:?}", result.gas_used.unwrap_or_default());
-        println!("status: {:?}", result.status.unwrap_or_default());
-
-        Ok(())
+    async fn token_uri(client: &Client, token_id: U256) -> Web3Result<String> {
+        client.token_uri(token_id).await
     }
 
-    pub async fn transfer(&self, to: Address, token_id: u128) -> Web3Result<()> {
-        let secret_key = SecretKey::from_str(&self.wallet_secret).unwrap();
-        let contract = contract(
-            self.contract_address.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-        );
-        let result = contract
-            .signed_call_with_confirmations(
-                "safeTransferFrom",
-                (self.wallet_address, to, token_id),
-                Options::with(|opt| {
-                    opt.gas = Some(U256::from(GAS_LIMIT));
-                    opt.gas_price = Some(U256::from(GAS_PRICE));
-                }),
-                1,
-                SecretKeyRef::from(&secret_key),
-            )
-            .await?;
-
-        println!("tx id: {:?}", result.transaction_hash);
-        println!("gas used: {:?}", result.gas_used.unwrap_or_default());
-        println!("status: {:?}", result.status.unwrap_or_default());
-
-        Ok(())
+    async fn balance_of(client: &Client, account: Address, token_id: U256) -> Web3Result<U256> {
+        let owned = client.is_owner(token_id, account).await?;
+        Ok(U256::from(owned as u8))
     }
 
-    pub async fn deploy(&self) -> Web3Result<()> {
-        let contract = deploy_contract(
-            self.wallet_secret.to_owned(),
-            include_bytes!("abi.json"),
-            self.network.to_owned(),
-            include_str!("bin").trim(),
-        )
-        .await?;
+    async fn submit_mint(
+        client: &Client,
+        content_hash: String,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        ensure_single(amount)?;
+        client.submit_mint(content_hash, options).await
+    }
 
-        println!("deployed to: {:?}", contract.address());
+    async fn submit_transfer(
+        client: &Client,
+        to: Address,
+        token_id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: &TxOptions,
+    ) -> Web3Result<PendingTx> {
+        ensure_single(amount)?;
+        client.submit_transfer(to, token_id, data, options).await
+    }
+}
 
-        Ok(())
+/// ERC721 tokens are unique, so there is never more than one to mint or move.
+fn ensure_single(amount: U256) -> Web3Result<()> {
+    if amount != U256::one() {
+        return Err(Error::Internal(format!(
+            "an ERC721 token is unique, amount must be 1 (got {})",
+            amount
+        )));
     }
+    Ok(())
 }
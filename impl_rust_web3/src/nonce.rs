@@ -0,0 +1,170 @@
+use crate::Web3Result;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use web3::transports::Http;
+use web3::types::{Address, BlockNumber, U256};
+use web3::Web3;
+
+#[derive(Debug, Default)]
+struct Account {
+    /// Next nonce that has never been handed out.
+    next: U256,
+    /// Nonces handed out whose transaction never reached the node, reused first.
+    released: BTreeSet<U256>,
+}
+
+/// Hands out nonces per (chain id, address) locally, so several transactions from one
+/// account can be signed and broadcast without waiting for the previous one to be mined.
+///
+/// Every call still reads the node's pending count, so transactions sent from elsewhere
+/// with the same key are picked up.
+#[derive(Clone, Debug, Default)]
+pub struct NonceManager {
+    accounts: Arc<Mutex<HashMap<(u64, Address), Account>>>,
+}
+
+impl NonceManager {
+    /// The instance shared by every client in this process.
+    pub fn shared() -> &'static NonceManager {
+        static SHARED: OnceLock<NonceManager> = OnceLock::new();
+        SHARED.get_or_init(NonceManager::default)
+    }
+
+    pub async fn next(
+        &self,
+        web3: &Web3<Http>,
+        chain_id: u64,
+        address: Address,
+    ) -> Web3Result<U256> {
+        let pending = web3
+            .eth()
+            .transaction_count(address, Some(BlockNumber::Pending))
+            .await?;
+
+        Ok(self.take(chain_id, address, pending))
+    }
+
+    /// The nonce to use next, given the node's `pending` transaction count.
+    fn take(&self, chain_id: u64, address: Address, pending: U256) -> U256 {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry((chain_id, address)).or_default();
+        // anything below the pending count has been used since it was released
+        account.released = account.released.split_off(&pending);
+        if let Some(nonce) = account.released.pop_first() {
+            return nonce;
+        }
+
+        let nonce = account.next.max(pending);
+        account.next = nonce + 1;
+        nonce
+    }
+
+    /// Returns a nonce whose transaction was never broadcast, so the next
+    /// transaction fills the gap instead of getting stuck behind it.
+    pub fn release(&self, chain_id: u64, address: Address, nonce: U256) {
+        let mut accounts = self.accounts.lock().unwrap();
+        if let Some(account) = accounts.get_mut(&(chain_id, address)) {
+            if nonce < account.next {
+                account.released.insert(nonce);
+            }
+        }
+    }
+
+    /// Forgets local state, e.g. after the node rejected a nonce as already used.
+    pub fn reset(&self, chain_id: u64, address: Address) {
+        self.accounts.lock().unwrap().remove(&(chain_id, address));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN_ID: u64 = 15;
+
+    fn take(nonces: &NonceManager, pending: u64) -> u64 {
+        nonces
+            .take(CHAIN_ID, Address::zero(), U256::from(pending))
+            .as_u64()
+    }
+
+    #[test]
+    fn hands_out_nonces_ahead_of_the_node() {
+        let nonces = NonceManager::default();
+
+        assert_eq!(take(&nonces, 3), 3);
+        assert_eq!(take(&nonces, 3), 4);
+        assert_eq!(take(&nonces, 4), 5);
+        // another account, or another chain, counts on its own
+        assert_eq!(
+            nonces.take(CHAIN_ID, Address::repeat_byte(1), U256::from(9)),
+            U256::from(9)
+        );
+        assert_eq!(
+            nonces.take(CHAIN_ID + 1, Address::zero(), U256::zero()),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn follows_transactions_sent_elsewhere() {
+        let nonces = NonceManager::default();
+
+        assert_eq!(take(&nonces, 0), 0);
+        assert_eq!(take(&nonces, 5), 5);
+        assert_eq!(take(&nonces, 5), 6);
+    }
+
+    #[test]
+    fn fills_released_gaps_lowest_first() {
+        let nonces = NonceManager::default();
+        for _ in 0..4 {
+            take(&nonces, 0);
+        }
+
+        nonces.release(CHAIN_ID, Address::zero(), U256::from(2));
+        nonces.release(CHAIN_ID, Address::zero(), U256::from(1));
+
+        assert_eq!(take(&nonces, 1), 1);
+        assert_eq!(take(&nonces, 1), 2);
+        assert_eq!(take(&nonces, 1), 4);
+    }
+
+    #[test]
+    fn drops_released_nonces_the_node_has_seen_since() {
+        let nonces = NonceManager::default();
+        for _ in 0..3 {
+            take(&nonces, 0);
+        }
+
+        nonces.release(CHAIN_ID, Address::zero(), U256::from(1));
+
+        // nonce 1 was used by a transaction sent elsewhere in the meantime
+        assert_eq!(take(&nonces, 2), 3);
+    }
+
+    #[test]
+    fn ignores_nonces_never_handed_out() {
+        let nonces = NonceManager::default();
+        nonces.release(CHAIN_ID, Address::zero(), U256::from(0));
+        assert_eq!(take(&nonces, 0), 0);
+
+        nonces.release(CHAIN_ID, Address::zero(), U256::from(5));
+
+        assert_eq!(take(&nonces, 0), 1);
+    }
+
+    #[test]
+    fn reset_starts_over_from_the_node() {
+        let nonces = NonceManager::default();
+        for _ in 0..3 {
+            take(&nonces, 0);
+        }
+        nonces.release(CHAIN_ID, Address::zero(), U256::from(1));
+
+        nonces.reset(CHAIN_ID, Address::zero());
+
+        assert_eq!(take(&nonces, 2), 2);
+        assert_eq!(take(&nonces, 2), 3);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::bindings::ContractEvent;
+use crate::nft_1155::bindings::events::{TransferBatch, TransferSingle};
+use crate::nft_721::bindings::events::Transfer;
+use crate::{Error, Web3Result};
+use web3::ethabi::{self, LogParam, RawLog, Token};
+use web3::types::{Address, Log, TransactionReceipt, H256, U256, U64};
+
+/// A log emitted by the called contract, decoded against its ABI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub address: Address,
+    pub params: Vec<LogParam>,
+}
+
+impl Event {
+    pub fn param(&self, name: &str) -> Option<&Token> {
+        self.params
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| &p.value)
+    }
+}
+
+/// A token created by a mint, i.e. a transfer from the zero address.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minted {
+    pub token_id: U256,
+    pub amount: U256,
+    pub to: Address,
+}
+
+impl Minted {
+    /// Reads ERC721 `Transfer` and ERC1155 `TransferSingle`/`TransferBatch` events.
+    fn from_event(event: &Event) -> Vec<Minted> {
+        let (from, to, tokens) = if let Some(e) = Transfer::from_event(event) {
+            (e.from, e.to, vec![(e.token_id, U256::one())])
+        } else if let Some(e) = TransferSingle::from_event(event) {
+            (e.from, e.to, vec![(e.id, e.value)])
+        } else if let Some(e) = TransferBatch::from_event(event) {
+            (e.from, e.to, e.ids.into_iter().zip(e.values).collect())
+        } else {
+            return vec![];
+        };
+        if !from.is_zero() {
+            return vec![];
+        }
+
+        tokens
+            .into_iter()
+            .map(|(token_id, amount)| Minted {
+                token_id,
+                amount,
+                to,
+            })
+            .collect()
+    }
+}
+
+/// A mint transaction together with the tokens it created.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintOutcome {
+    pub tx: TxOutcome,
+    pub minted: Vec<Minted>,
+}
+
+impl MintOutcome {
+    pub(crate) fn new(tx: TxOutcome, contract: Address) -> Web3Result<Self> {
+        let minted = tx.minted(contract);
+        if tx.succeeded() && !tx.is_pending() && minted.is_empty() {
+            return Err(Error::Decode(format!(
+                "no mint event in transaction {:?}",
+                tx.transaction_hash
+            )));
+        }
+
+        Ok(MintOutcome { tx, minted })
+    }
+}
+
+/// The gas limit a transaction was sent with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GasLimit {
+    pub limit: U256,
+    /// The node's `eth_estimateGas`, unless the limit was fixed by options or config.
+    pub estimate: Option<U256>,
+}
+
+/// What a mined transaction did. Only the hash is set while it is pending.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxOutcome {
+    pub transaction_hash: H256,
+    /// Set for transactions sent by this crate, not for ones looked up by hash.
+    pub gas: Option<GasLimit>,
+    pub block_number: Option<U64>,
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+    pub status: Option<U64>,
+    /// Logs that match an event in the contract ABI; others are skipped.
+    pub events: Vec<Event>,
+    pub contract_address: Option<Address>,
+}
+
+impl TxOutcome {
+    pub fn new(receipt: TransactionReceipt, abi: &ethabi::Contract) -> Self {
+        let events = receipt
+            .logs
+            .iter()
+            .filter_map(|log| decode_log(log, abi))
+            .collect();
+
+        TxOutcome {
+            transaction_hash: receipt.transaction_hash,
+            gas: None,
+            block_number: receipt.block_number,
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+            status: receipt.status,
+            events,
+            contract_address: receipt.contract_address,
+        }
+    }
+
+    /// A transaction that was broadcast but not waited for.
+    pub(crate) fn pending(transaction_hash: H256) -> Self {
+        TxOutcome {
+            transaction_hash,
+            gas: None,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+            status: None,
+            events: vec![],
+            contract_address: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.block_number.is_none()
+    }
+
+    /// What the sender paid for gas, in wei.
+    pub fn fee(&self) -> Option<U256> {
+        Some(self.gas_used? * self.effective_gas_price?)
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status.is_none_or(|s| !s.is_zero())
+    }
+
+    /// Tokens minted by `contract` in this transaction, in log order.
+    pub fn minted(&self, contract: Address) -> Vec<Minted> {
+        self.events
+            .iter()
+            .filter(|e| e.address == contract)
+            .flat_map(Minted::from_event)
+            .collect()
+    }
+
+    pub fn events<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Event> {
+        self.events.iter().filter(move |e| e.name == name)
+    }
+}
+
+fn decode_log(log: &Log, abi: &ethabi::Contract) -> Option<Event> {
+    let topic = log.topics.first()?;
+    let event = abi.events().find(|e| e.signature() == *topic)?;
+    let parsed = event
+        .parse_log(RawLog {
+            topics: log.topics.clone(),
+            data: log.data.0.clone(),
+        })
+        .ok()?;
+
+    Some(Event {
+        name: event.name.clone(),
+        address: log.address,
+        params: parsed.params,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::ethabi::{encode, Contract};
+    use web3::types::Bytes;
+
+    fn nft_721() -> Contract {
+        Contract::load(&include_bytes!("nft_721/abi.json")[..]).unwrap()
+    }
+
+    fn nft_1155() -> Contract {
+        Contract::load(&include_bytes!("nft_1155/abi.json")[..]).unwrap()
+    }
+
+    fn contract() -> Address {
+        Address::repeat_byte(0x33)
+    }
+
+    fn holder() -> Address {
+        Address::repeat_byte(0x44)
+    }
+
+    fn topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    /// A log of event `name` in `abi`, emitted by `address`.
+    fn log(abi: &Contract, name: &str, address: Address, topics: &[H256], data: &[Token]) -> Log {
+        let mut all = vec![abi.event(name).unwrap().signature()];
+        all.extend_from_slice(topics);
+        Log {
+            address,
+            topics: all,
+            data: Bytes(encode(data)),
+            block_hash: None,
+            block_number: None,
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: None,
+            transaction_log_index: None,
+            log_type: None,
+            removed: None,
+        }
+    }
+
+    fn transfer(from: Address, token_id: u64, address: Address) -> Log {
+        let token_id = H256::from_low_u64_be(token_id);
+        log(
+            &nft_721(),
+            "Transfer",
+            address,
+            &[topic(from), topic(holder()), token_id],
+            &[],
+        )
+    }
+
+    fn mined(logs: Vec<Log>, abi: &Contract) -> TxOutcome {
+        let receipt = TransactionReceipt {
+            transaction_hash: H256::repeat_byte(0xaa),
+            block_number: Some(U64::from(100)),
+            status: Some(U64::one()),
+            logs,
+            ..Default::default()
+        };
+        TxOutcome::new(receipt, abi)
+    }
+
+    #[test]
+    fn reads_erc721_mints() {
+        let tx = mined(vec![transfer(Address::zero(), 7, contract())], &nft_721());
+
+        assert_eq!(
+            MintOutcome::new(tx, contract()).unwrap().minted,
+            vec![Minted {
+                token_id: U256::from(7),
+                amount: U256::one(),
+                to: holder(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reads_erc1155_single_and_batch_mints() {
+        let abi = nft_1155();
+        let operator = topic(Address::repeat_byte(0x55));
+        let single = log(
+            &abi,
+            "TransferSingle",
+            contract(),
+            &[operator, topic(Address::zero()), topic(holder())],
+            &[Token::Uint(U256::from(1)), Token::Uint(U256::from(10))],
+        );
+        let batch = log(
+            &abi,
+            "TransferBatch",
+            contract(),
+            &[operator, topic(Address::zero()), topic(holder())],
+            &[
+                Token::Array(vec![Token::Uint(U256::from(2)), Token::Uint(U256::from(3))]),
+                Token::Array(vec![
+                    Token::Uint(U256::from(20)),
+                    Token::Uint(U256::from(30)),
+                ]),
+            ],
+        );
+        let tx = mined(vec![single, batch], &abi);
+
+        let minted: Vec<_> = MintOutcome::new(tx, contract())
+            .unwrap()
+            .minted
+            .iter()
+            .map(|m| (m.token_id.as_u64(), m.amount.as_u64(), m.to))
+            .collect();
+        assert_eq!(
+            minted,
+            vec![(1, 10, holder()), (2, 20, holder()), (3, 30, holder())]
+        );
+    }
+
+    #[test]
+    fn ignores_transfers_and_other_contracts() {
+        let tx = mined(
+            vec![
+                transfer(Address::repeat_byte(0x66), 1, contract()),
+                transfer(Address::zero(), 2, Address::repeat_byte(0x77)),
+                transfer(Address::zero(), 3, contract()),
+            ],
+            &nft_721(),
+        );
+
+        let minted = tx.minted(contract());
+        assert_eq!(minted.len(), 1);
+        assert_eq!(minted[0].token_id, U256::from(3));
+    }
+
+    #[test]
+    fn fails_without_a_mint_event() {
+        let tx = mined(
+            vec![transfer(Address::repeat_byte(0x66), 1, contract())],
+            &nft_721(),
+        );
+
+        match MintOutcome::new(tx, contract()) {
+            Err(Error::Decode(message)) => assert!(message.contains("no mint event")),
+            other => panic!("expected a decode error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_pending_and_reverted_mints_without_events() {
+        let pending = TxOutcome::pending(H256::repeat_byte(0xaa));
+        assert!(MintOutcome::new(pending, contract())
+            .unwrap()
+            .minted
+            .is_empty());
+
+        let mut reverted = mined(vec![], &nft_721());
+        reverted.status = Some(U64::zero());
+        assert!(MintOutcome::new(reverted, contract())
+            .unwrap()
+            .minted
+            .is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{Error, GasLimit, TxOutcome, Web3Result};
+use prelude::config::WaitPolicy;
+use web3::ethabi;
+use web3::transports::Http;
+use web3::types::{TransactionReceipt, H256};
+use web3::Web3;
+
+/// A broadcast transaction whose receipt can be awaited later.
+#[derive(Clone, Debug)]
+pub struct PendingTx {
+    web3: Web3<Http>,
+    hash: H256,
+    abi: ethabi::Contract,
+    wait: WaitPolicy,
+    gas: Option<GasLimit>,
+}
+
+impl PendingTx {
+    /// Follows `hash`, decoding its logs against `abi` once mined.
+    pub fn new(web3: Web3<Http>, hash: H256, abi: ethabi::Contract, wait: WaitPolicy) -> Self {
+        PendingTx {
+            web3,
+            hash,
+            abi,
+            wait,
+            gas: None,
+        }
+    }
+
+    pub(crate) fn with_gas(mut self, gas: GasLimit) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    pub fn hash(&self) -> H256 {
+        self.hash
+    }
+
+    /// The gas limit it was sent with, if it was sent by this crate.
+    pub fn gas(&self) -> Option<GasLimit> {
+        self.gas
+    }
+
+    pub fn wait_policy(&self) -> &WaitPolicy {
+        &self.wait
+    }
+
+    /// Waits for the configured confirmations, but at least until the transaction is mined.
+    pub async fn receipt(&self) -> Web3Result<TransactionReceipt> {
+        let confirmations = self.wait.confirmations.max(1);
+        tokio::time::timeout(self.wait.timeout, self.poll(confirmations))
+            .await
+            .map_err(|_| Error::NotConfirmed(self.hash))?
+    }
+
+    /// Like [`PendingTx::receipt`], decoded against the ABI of the called contract.
+    pub async fn outcome(&self) -> Web3Result<TxOutcome> {
+        let receipt = self.receipt().await?;
+        Ok(TxOutcome {
+            gas: self.gas,
+            ..TxOutcome::new(receipt, &self.abi)
+        })
+    }
+
+    /// Waits as configured; with 0 confirmations this returns a pending outcome at once.
+    pub(crate) async fn settle(&self) -> Web3Result<TxOutcome> {
+        if self.wait.confirmations == 0 {
+            return Ok(TxOutcome {
+                gas: self.gas,
+                ..TxOutcome::pending(self.hash)
+            });
+        }
+        self.outcome().await
+    }
+
+    async fn poll(&self, confirmations: u64) -> Web3Result<TransactionReceipt> {
+        loop {
+            if let Some(receipt) = self.web3.eth().transaction_receipt(self.hash).await? {
+                if let Some(block_number) = receipt.block_number {
+                    let latest = self.web3.eth().block_number().await?;
+                    if block_number.as_u64() + confirmations <= latest.as_u64() + 1 {
+                        return Ok(receipt);
+                    }
+                }
+            }
+            tokio::time::sleep(self.wait.poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+use web3::ethabi::{self, ParamType, Token};
+use web3::types::{Bytes, U256};
+
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+const NOT_OWNER_REASON: &str = "Ownable: caller is not the owner";
+
+/// Why a call reverted, as far as it could be decoded from the revert data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Revert {
+    /// `revert("...")` / `require(cond, "...")`, i.e. `Error(string)`.
+    Reason(String),
+    /// OpenZeppelin `onlyOwner` rejected the sender.
+    NotOwner,
+    /// A compiler inserted check failed, i.e. `Panic(uint256)`.
+    Panic(U256),
+    /// A custom `error Name(...)` declared in the contract ABI.
+    Custom { name: String, params: Vec<Token> },
+    /// Revert data that could not be decoded, possibly empty.
+    Raw(Bytes),
+}
+
+impl Revert {
+    /// Decodes the builtin `Error(string)` and `Panic(uint256)` reverts.
+    pub fn decode(data: &[u8]) -> Self {
+        if data.len() < 4 {
+            return Revert::Raw(Bytes(data.to_vec()));
+        }
+
+        let (selector, args) = data.split_at(4);
+        if selector == ERROR_SELECTOR {
+            if let Ok(tokens) = ethabi::decode(&[ParamType::String], args) {
+                if let Some(Token::String(reason)) = tokens.into_iter().next() {
+                    return Revert::from_reason(reason);
+                }
+            }
+        }
+        if selector == PANIC_SELECTOR {
+            if let Ok(tokens) = ethabi::decode(&[ParamType::Uint(256)], args) {
+                if let Some(Token::Uint(code)) = tokens.into_iter().next() {
+                    return Revert::Panic(code);
+                }
+            }
+        }
+        Revert::Raw(Bytes(data.to_vec()))
+    }
+
+    /// Like [`Revert::decode`], additionally matching custom errors declared in `abi`.
+    pub fn decode_with(data: &[u8], abi: &ethabi::Contract) -> Self {
+        match Revert::decode(data) {
+            Revert::Raw(raw) => Revert::custom(&raw.0, abi).unwrap_or(Revert::Raw(raw)),
+            revert => revert,
+        }
+    }
+
+    /// Re-decodes undecoded revert data against `abi`.
+    pub(crate) fn resolve(self, abi: &ethabi::Contract) -> Self {
+        match self {
+            Revert::Raw(raw) => Revert::decode_with(&raw.0, abi),
+            revert => revert,
+        }
+    }
+
+    /// Extracts the revert from a node's JSON-RPC error, if the error is one.
+    pub(crate) fn from_rpc(code: i64, message: &str, data: Option<&str>) -> Option<Self> {
+        if code != 3 && !message.to_lowercase().contains("revert") {
+            return None;
+        }
+
+        if let Some(data) = data.and_then(|d| hex::decode(d.trim_start_matches("0x")).ok()) {
+            if !data.is_empty() {
+                return Some(Revert::decode(&data));
+            }
+        }
+        match message.split_once("reverted: ") {
+            Some((_, reason)) => Some(Revert::from_reason(reason.to_string())),
+            None => Some(Revert::Raw(Bytes::default())),
+        }
+    }
+
+    fn from_reason(reason: String) -> Self {
+        if reason == NOT_OWNER_REASON {
+            Revert::NotOwner
+        } else {
+            Revert::Reason(reason)
+        }
+    }
+
+    fn custom(data: &[u8], abi: &ethabi::Contract) -> Option<Self> {
+        if data.len() < 4 {
+            return None;
+        }
+
+        let (selector, args) = data.split_at(4);
+        abi.errors()
+            .find(|e| e.signature()[..4] == *selector)
+            .and_then(|e| {
+                let params = e.decode(args).ok()?;
+                Some(Revert::Custom {
+                    name: e.name.clone(),
+                    params,
+                })
+            })
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Revert::Reason(reason) => write!(f, "{}", reason),
+            Revert::NotOwner => write!(f, "{}", NOT_OWNER_REASON),
+            Revert::Panic(code) => write!(f, "panic 0x{:02x} ({})", code, panic_reason(*code)),
+            Revert::Custom { name, params } => {
+                let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+                write!(f, "{}({})", name, params.join(", "))
+            }
+            Revert::Raw(data) if data.0.is_empty() => write!(f, "no reason given"),
+            Revert::Raw(data) => write!(f, "0x{}", hex::encode(&data.0)),
+        }
+    }
+}
+
+/// See https://docs.soliditylang.org/en/latest/control-structures.html#panic-via-assert-and-error-via-require
+fn panic_reason(code: U256) -> &'static str {
+    match code.low_u64() {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to uninitialized function",
+        _ => "unknown panic",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(name: &str, params: &[ParamType], tokens: &[Token]) -> Vec<u8> {
+        let mut data = ethabi::short_signature(name, params).to_vec();
+        data.extend(ethabi::encode(tokens));
+        data
+    }
+
+    #[test]
+    fn decodes_builtin_reverts() {
+        let reason = encoded(
+            "Error",
+            &[ParamType::String],
+            &[Token::String("sold out".to_string())],
+        );
+        assert_eq!(
+            Revert::decode(&reason),
+            Revert::Reason("sold out".to_string())
+        );
+
+        let not_owner = encoded(
+            "Error",
+            &[ParamType::String],
+            &[Token::String(NOT_OWNER_REASON.to_string())],
+        );
+        assert_eq!(Revert::decode(&not_owner), Revert::NotOwner);
+
+        let panic = encoded(
+            "Panic",
+            &[ParamType::Uint(256)],
+            &[Token::Uint(U256::from(0x11))],
+        );
+        assert_eq!(Revert::decode(&panic), Revert::Panic(U256::from(0x11)));
+        assert_eq!(
+            Revert::decode(&panic).to_string(),
+            "panic 0x11 (arithmetic overflow or underflow)"
+        );
+    }
+
+    #[test]
+    fn keeps_undecodable_data_raw() {
+        assert_eq!(Revert::decode(&[]), Revert::Raw(Bytes::default()));
+        assert_eq!(
+            Revert::decode(&[0xde, 0xad]),
+            Revert::Raw(Bytes(vec![0xde, 0xad]))
+        );
+        // Error(string) selector with a body that is not an encoded string
+        assert_eq!(
+            Revert::decode(&ERROR_SELECTOR),
+            Revert::Raw(Bytes(ERROR_SELECTOR.to_vec()))
+        );
+    }
+
+    #[test]
+    fn decodes_custom_errors_from_the_abi() {
+        let abi = ethabi::Contract::load(
+            &br#"[{"type":"error","name":"Unauthorized","inputs":[{"name":"account","type":"address"}]}]"#[..],
+        )
+        .unwrap();
+        let account = Token::Address([0x11; 20].into());
+        let data = encoded(
+            "Unauthorized",
+            &[ParamType::Address],
+            std::slice::from_ref(&account),
+        );
+
+        assert_eq!(Revert::decode(&data), Revert::Raw(Bytes(data.clone())));
+        assert_eq!(
+            Revert::decode_with(&data, &abi),
+            Revert::Custom {
+                name: "Unauthorized".to_string(),
+                params: vec![account],
+            }
+        );
+        assert_eq!(
+            Revert::decode(&data).resolve(&abi),
+            Revert::decode_with(&data, &abi)
+        );
+    }
+
+    #[test]
+    fn reads_reverts_from_rpc_errors() {
+        let reason = encoded(
+            "Error",
+            &[ParamType::String],
+            &[Token::String("sold out".to_string())],
+        );
+        let data = format!("0x{}", hex::encode(&reason));
+        assert_eq!(
+            Revert::from_rpc(3, "execution reverted", Some(&data)),
+            Some(Revert::Reason("sold out".to_string()))
+        );
+
+        // nodes that only put the reason in the message
+        assert_eq!(
+            Revert::from_rpc(
+                -32000,
+                "execution reverted: Ownable: caller is not the owner",
+                None
+            ),
+            Some(Revert::NotOwner)
+        );
+        assert_eq!(
+            Revert::from_rpc(-32000, "execution reverted", Some("0x")),
+            Some(Revert::Raw(Bytes::default()))
+        );
+
+        assert_eq!(Revert::from_rpc(-32000, "nonce too low", None), None);
+    }
+}
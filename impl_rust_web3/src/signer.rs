@@ -0,0 +1,337 @@
+use crate::{Error, Web3Result};
+use async_trait::async_trait;
+use coins_bip32::ecdsa::SigningKey;
+use coins_bip39::{English, Mnemonic};
+use secp256k1::SecretKey;
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use web3::signing::{Key, SecretKeyRef};
+use web3::transports::Http;
+use web3::types::{Address, BlockNumber, Bytes, TransactionParameters, TransactionRequest};
+use web3::{Transport, Web3};
+
+/// First account of the first wallet, as used by MetaMask and most hardware wallets.
+pub const DEFAULT_HD_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Signs transactions on behalf of one account.
+#[async_trait]
+pub trait Signer: fmt::Debug + Send + Sync {
+    fn address(&self) -> Address;
+
+    /// Signs `tx`, which already carries its gas limit, fees and chain id, and returns
+    /// the raw transaction ready for `eth_sendRawTransaction`.
+    async fn sign_transaction(
+        &self,
+        web3: &Web3<Http>,
+        tx: TransactionParameters,
+    ) -> Web3Result<Bytes>;
+}
+
+/// Fails when `expected` is given and is not the account `signer` signs for, so a
+/// configured address can never silently diverge from the key in use.
+pub fn ensure_address(signer: &dyn Signer, expected: Option<Address>) -> Web3Result<()> {
+    match expected {
+        Some(expected) if expected != signer.address() => Err(Error::AddressMismatch {
+            expected,
+            signer: signer.address(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// A signer holding the private key in memory, however it was obtained.
+#[derive(Clone)]
+pub struct LocalSigner {
+    key: SecretKey,
+    address: Address,
+}
+
+impl LocalSigner {
+    pub fn new(key: SecretKey) -> Self {
+        let address = SecretKeyRef::new(&key).address();
+        LocalSigner { key, address }
+    }
+
+    /// From a hex encoded private key, with or without `0x`.
+    pub fn from_hex(secret: &str) -> Web3Result<Self> {
+        let key = SecretKey::from_str(secret.trim().trim_start_matches("0x"))
+            .map_err(|e| Error::Signing(format!("invalid private key: {}", e)))?;
+        Ok(LocalSigner::new(key))
+    }
+
+    /// From an encrypted JSON keystore (Web3 Secret Storage), as written by geth.
+    pub fn from_keystore<P: AsRef<Path>>(path: P, password: &str) -> Web3Result<Self> {
+        let secret = decrypt_keystore(path.as_ref(), password)?;
+        LocalSigner::from_slice(&secret)
+    }
+
+    /// From a BIP-39 mnemonic, deriving the key at the BIP-44 `hd_path`.
+    pub fn from_mnemonic(
+        phrase: &str,
+        hd_path: &str,
+        passphrase: Option<&str>,
+    ) -> Web3Result<Self> {
+        let mnemonic = Mnemonic::<English>::new_from_phrase(phrase.trim())
+            .map_err(|e| Error::Signing(format!("invalid mnemonic: {}", e)))?;
+        let xpriv = mnemonic
+            .derive_key(hd_path, passphrase)
+            .map_err(|e| Error::Signing(format!("{}: {}", hd_path, e)))?;
+        let key: &SigningKey = xpriv.as_ref();
+        LocalSigner::from_slice(&key.to_bytes())
+    }
+
+    fn from_slice(secret: &[u8]) -> Web3Result<Self> {
+        let key = SecretKey::from_slice(secret)
+            .map_err(|e| Error::Signing(format!("invalid private key: {}", e)))?;
+        Ok(LocalSigner::new(key))
+    }
+}
+
+pub(crate) fn decrypt_keystore(path: &Path, password: &str) -> Web3Result<Vec<u8>> {
+    eth_keystore::decrypt_key(path, password).map_err(|e| match e {
+        eth_keystore::KeystoreError::MacMismatch => {
+            Error::Signing(format!("{}: wrong password", path.display()))
+        }
+        e => Error::Signing(format!("{}: {}", path.display(), e)),
+    })
+}
+
+impl fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        web3: &Web3<Http>,
+        tx: TransactionParameters,
+    ) -> Web3Result<Bytes> {
+        let signed = web3.accounts().sign_transaction(tx, &self.key).await?;
+        Ok(signed.raw_transaction)
+    }
+}
+
+/// Delegates signing to a JSON-RPC service exposing `eth_signTransaction`, such as
+/// clef or web3signer, so the key never reaches this process.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    remote: Web3<Http>,
+    address: Address,
+}
+
+impl RemoteSigner {
+    /// Connects to the signer at `url`. Without `address`, the first account it
+    /// reports from `eth_accounts` is used.
+    pub async fn connect(url: &str, address: Option<Address>) -> Web3Result<Self> {
+        let remote = Web3::new(Http::new(url)?);
+        let address = match address {
+            Some(address) => address,
+            None => remote
+                .eth()
+                .accounts()
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::Signing(format!("{} has no accounts", url)))?,
+        };
+
+        Ok(RemoteSigner { remote, address })
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        web3: &Web3<Http>,
+        tx: TransactionParameters,
+    ) -> Web3Result<Bytes> {
+        let nonce = match tx.nonce {
+            Some(nonce) => nonce,
+            None => {
+                web3.eth()
+                    .transaction_count(self.address, Some(BlockNumber::Pending))
+                    .await?
+            }
+        };
+        let request = TransactionRequest {
+            from: self.address,
+            to: tx.to,
+            gas: Some(tx.gas),
+            gas_price: tx.gas_price,
+            value: Some(tx.value),
+            data: Some(tx.data),
+            nonce: Some(nonce),
+            condition: None,
+            transaction_type: tx.transaction_type,
+            access_list: tx.access_list,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        };
+        let mut request = serde_json::to_value(request)
+            .map_err(|e| Error::Internal(format!("failed to encode transaction: {}", e)))?;
+        if let (Value::Object(fields), Some(chain_id)) = (&mut request, tx.chain_id) {
+            fields.insert(
+                "chainId".to_string(),
+                Value::String(format!("{:#x}", chain_id)),
+            );
+        }
+
+        let signed = self
+            .remote
+            .transport()
+            .execute("eth_signTransaction", vec![request])
+            .await?;
+        // geth and clef answer `{ raw, tx }`, others just the raw hex
+        let raw = match &signed {
+            Value::Object(fields) => fields.get("raw").and_then(Value::as_str),
+            value => value.as_str(),
+        };
+        raw.and_then(|raw| hex::decode(raw.trim_start_matches("0x")).ok())
+            .map(Bytes)
+            .ok_or_else(|| {
+                Error::Signing(format!("unexpected eth_signTransaction result: {}", signed))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use web3::types::U256;
+
+    const ACCOUNT: &str = "0x1341048e3d37046ca18a09efb154ea9771744f41";
+
+    /// A JSON-RPC node on a free local port: `eth_accounts` lists [`ACCOUNT`] and
+    /// `eth_signTransaction` answers `signed`. Every request it receives is recorded.
+    fn stub(signed: Value) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let result = match request["method"].as_str() {
+                    Some("eth_accounts") => json!([ACCOUNT]),
+                    _ => signed.clone(),
+                };
+                let response =
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string();
+                recorded.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn transaction(chain_id: Option<u64>) -> TransactionParameters {
+        TransactionParameters {
+            nonce: Some(U256::from(7)),
+            to: Some(Address::repeat_byte(0x22)),
+            value: U256::from(1000),
+            chain_id,
+            ..Default::default()
+        }
+    }
+
+    async fn sign(signed: Value, chain_id: Option<u64>) -> (Web3Result<Bytes>, Vec<Value>) {
+        let (url, requests) = stub(signed);
+        let signer = RemoteSigner::connect(&url, None).await.unwrap();
+        let web3 = Web3::new(Http::new(&url).unwrap());
+        let result = signer.sign_transaction(&web3, transaction(chain_id)).await;
+        let requests = requests.lock().unwrap().clone();
+        (result, requests)
+    }
+
+    #[tokio::test]
+    async fn uses_the_first_remote_account() {
+        let (url, requests) = stub(Value::Null);
+        let signer = RemoteSigner::connect(&url, None).await.unwrap();
+
+        assert_eq!(signer.address(), Address::from_str(ACCOUNT).unwrap());
+        assert_eq!(requests.lock().unwrap()[0]["method"], "eth_accounts");
+    }
+
+    #[tokio::test]
+    async fn reads_raw_from_a_geth_style_reply() {
+        let (result, requests) = sign(
+            json!({ "raw": "0x02f801", "tx": { "nonce": "0x7" } }),
+            Some(15),
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), Bytes(vec![0x02, 0xf8, 0x01]));
+        let request = &requests[1];
+        assert_eq!(request["method"], "eth_signTransaction");
+        assert_eq!(request["params"][0]["from"], ACCOUNT);
+        assert_eq!(request["params"][0]["nonce"], "0x7");
+        assert_eq!(request["params"][0]["chainId"], "0xf");
+    }
+
+    #[tokio::test]
+    async fn reads_a_plain_hex_reply() {
+        let (result, requests) = sign(json!("0xf86b07"), None).await;
+
+        assert_eq!(result.unwrap(), Bytes(vec![0xf8, 0x6b, 0x07]));
+        assert!(requests[1]["params"][0].get("chainId").is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_replies() {
+        for signed in [json!(42), json!({ "tx": {} }), json!("0xzz")] {
+            let (result, _) = sign(signed.clone(), Some(15)).await;
+
+            match result {
+                Err(Error::Signing(message)) => assert!(
+                    message.contains("unexpected eth_signTransaction result"),
+                    "{}",
+                    message
+                ),
+                other => panic!("{} gave {:?}", signed, other),
+            }
+        }
+    }
+}
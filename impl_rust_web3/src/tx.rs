@@ -0,0 +1,364 @@
+use crate::fee::{Fee, FeeStrategy};
+use crate::signer::Signer;
+use crate::{call_request, connect, Error, Revert, TxOptions, TxOutcome, Web3Result};
+use prelude::config::{NetworkConfig, WaitPolicy};
+use std::time;
+use web3::ethabi;
+use web3::transports::Http;
+use web3::types::{
+    BlockId, BlockNumber, Bytes, Transaction, TransactionId, TransactionParameters,
+    TransactionReceipt, H256, U256, U64,
+};
+use web3::Web3;
+
+/// Gas of a plain value transfer, which is all a cancellation needs.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Fee a replacement offers, in per mille of the original. Geth requires a 10% increase;
+/// other clients ask for 12.5%, so the higher one is used.
+const REPLACEMENT_FEE_PERMILLE: u64 = 1125;
+
+/// A transaction re-signed under the nonce of a pending one, and whichever of the two
+/// was mined.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacement {
+    pub original: H256,
+    /// `None` when the node refused the replacement because the original was mined first.
+    pub replacement: Option<H256>,
+    /// The mined transaction, either `original` or `replacement`.
+    pub outcome: TxOutcome,
+}
+
+impl Replacement {
+    /// Whether the replacement won, as opposed to the original being mined first.
+    pub fn replaced(&self) -> bool {
+        Some(self.outcome.transaction_hash) == self.replacement
+    }
+}
+
+/// A replacement that was broadcast, or refused because the original was mined first,
+/// before waiting for either to be mined.
+#[derive(Clone, Debug)]
+pub struct PendingReplacement {
+    web3: Web3<Http>,
+    original: H256,
+    sent: Sent,
+    wait: WaitPolicy,
+}
+
+#[derive(Clone, Debug)]
+enum Sent {
+    Replacement(H256),
+    Refused(Box<TxOutcome>),
+}
+
+impl PendingReplacement {
+    pub fn original(&self) -> H256 {
+        self.original
+    }
+
+    /// `None` when the node refused the replacement because the original was mined first.
+    pub fn replacement(&self) -> Option<H256> {
+        match self.sent {
+            Sent::Replacement(hash) => Some(hash),
+            Sent::Refused(_) => None,
+        }
+    }
+
+    /// Waits until the original or the replacement is mined.
+    pub async fn wait(self) -> Web3Result<Replacement> {
+        let replacement = match self.sent {
+            Sent::Replacement(hash) => hash,
+            Sent::Refused(outcome) => {
+                return Ok(Replacement {
+                    original: self.original,
+                    replacement: None,
+                    outcome: *outcome,
+                })
+            }
+        };
+        let receipt = tokio::time::timeout(
+            self.wait.timeout,
+            wait_for_either(
+                &self.web3,
+                [replacement, self.original],
+                self.wait.poll_interval,
+            ),
+        )
+        .await
+        .map_err(|_| Error::NotConfirmed(replacement))??;
+        Ok(Replacement {
+            original: self.original,
+            replacement: Some(replacement),
+            outcome: TxOutcome::new(receipt, &known_abi()?),
+        })
+    }
+}
+
+/// Where a transaction is, as seen by the node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxStatus {
+    /// Known to the node but not yet in a block.
+    Pending,
+    Mined {
+        outcome: TxOutcome,
+        confirmations: u64,
+    },
+    /// Mined, but reverted.
+    Failed {
+        outcome: TxOutcome,
+        confirmations: u64,
+        /// Why it reverted, if replaying it reproduces the revert.
+        revert: Option<Revert>,
+    },
+}
+
+/// Looks up `hash`, decoding its logs against the NFT contract ABIs. The revert reason of
+/// a failed transaction is recovered by replaying it with `eth_call` on the state before
+/// its block, which can differ from what it actually ran against.
+pub async fn status(network: &NetworkConfig, hash: H256) -> Web3Result<TxStatus> {
+    let web3 = connect(network)?;
+    let tx = web3
+        .eth()
+        .transaction(TransactionId::Hash(hash))
+        .await?
+        .ok_or_else(|| Error::Internal(format!("transaction {:?} not found", hash)))?;
+    let receipt = match web3.eth().transaction_receipt(hash).await? {
+        Some(receipt) if receipt.block_number.is_some() => receipt,
+        _ => return Ok(TxStatus::Pending),
+    };
+
+    let abi = known_abi()?;
+    let block_number = receipt.block_number.unwrap_or_default().as_u64();
+    let latest = web3.eth().block_number().await?.as_u64();
+    let confirmations = (latest + 1).saturating_sub(block_number);
+    let outcome = TxOutcome::new(receipt, &abi);
+    if outcome.succeeded() {
+        return Ok(TxStatus::Mined {
+            outcome,
+            confirmations,
+        });
+    }
+
+    let revert = replay(&web3, &tx, block_number.saturating_sub(1))
+        .await
+        .map(|revert| revert.resolve(&abi));
+    Ok(TxStatus::Failed {
+        outcome,
+        confirmations,
+        revert,
+    })
+}
+
+/// Runs `tx` again at `block_number` and returns the revert it hits, if any.
+async fn replay(web3: &Web3<Http>, tx: &Transaction, block_number: u64) -> Option<Revert> {
+    let mut request = call_request(
+        tx.from.unwrap_or_default(),
+        &TransactionParameters {
+            to: tx.to,
+            value: tx.value,
+            data: tx.input.clone(),
+            ..Default::default()
+        },
+    );
+    request.gas = Some(tx.gas);
+    let block = BlockId::Number(BlockNumber::Number(U64::from(block_number)));
+
+    match web3
+        .eth()
+        .call(request, Some(block))
+        .await
+        .map_err(Error::from)
+    {
+        Err(Error::Revert(revert)) => Some(revert),
+        _ => None,
+    }
+}
+
+/// The NFT contract ABIs merged into one, so logs of either can be decoded.
+fn known_abi() -> Web3Result<ethabi::Contract> {
+    let mut abi = ethabi::Contract::load(&include_bytes!("nft_721/abi.json")[..])?;
+    let nft_1155 = ethabi::Contract::load(&include_bytes!("nft_1155/abi.json")[..])?;
+    for (name, events) in nft_1155.events {
+        let known = abi.events.entry(name).or_default();
+        for event in events {
+            if !known.contains(&event) {
+                known.push(event);
+            }
+        }
+    }
+    for (name, errors) in nft_1155.errors {
+        abi.errors.entry(name).or_default().extend(errors);
+    }
+
+    Ok(abi)
+}
+
+/// Re-sends the pending transaction `hash` unchanged but with higher fees.
+pub async fn speed_up(
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    hash: H256,
+    options: &TxOptions,
+) -> Web3Result<PendingReplacement> {
+    let web3 = connect(network)?;
+    let original = pending_transaction(&web3, signer, hash).await?;
+    let tx = TransactionParameters {
+        to: original.to,
+        value: original.value,
+        data: original.input.clone(),
+        gas: original.gas,
+        ..Default::default()
+    };
+
+    replace(web3, network, signer, &original, tx, options).await
+}
+
+/// Replaces the pending transaction `hash` with a zero-value transfer to the signer itself,
+/// so its nonce is used up without doing anything.
+pub async fn cancel(
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    hash: H256,
+    options: &TxOptions,
+) -> Web3Result<PendingReplacement> {
+    let web3 = connect(network)?;
+    let original = pending_transaction(&web3, signer, hash).await?;
+    let tx = TransactionParameters {
+        to: Some(signer.address()),
+        value: U256::zero(),
+        data: Bytes::default(),
+        gas: U256::from(TRANSFER_GAS),
+        ..Default::default()
+    };
+
+    replace(web3, network, signer, &original, tx, options).await
+}
+
+/// Looks `hash` up and checks that it is still pending and was sent by `signer`.
+async fn pending_transaction(
+    web3: &Web3<Http>,
+    signer: &dyn Signer,
+    hash: H256,
+) -> Web3Result<Transaction> {
+    let tx = web3
+        .eth()
+        .transaction(TransactionId::Hash(hash))
+        .await?
+        .ok_or_else(|| Error::Internal(format!("transaction {:?} not found", hash)))?;
+    if let Some(block_number) = tx.block_number {
+        return Err(Error::Internal(format!(
+            "transaction {:?} is already mined in block {}",
+            hash, block_number
+        )));
+    }
+    if tx.from != Some(signer.address()) {
+        return Err(Error::AddressMismatch {
+            expected: tx.from.unwrap_or_default(),
+            signer: signer.address(),
+        });
+    }
+
+    Ok(tx)
+}
+
+async fn replace(
+    web3: Web3<Http>,
+    network: &NetworkConfig,
+    signer: &dyn Signer,
+    original: &Transaction,
+    mut tx: TransactionParameters,
+    options: &TxOptions,
+) -> Web3Result<PendingReplacement> {
+    let mut strategy = FeeStrategy::new(network.gas());
+    if let Some(speed) = options.fee_speed {
+        strategy.speed = speed;
+    }
+    let fee = strategy.estimate(&web3, network.network().eip1559).await?;
+    bump(fee, original).apply(&mut tx);
+    tx.nonce = Some(original.nonce);
+    tx.chain_id = Some(network.chain_id());
+
+    let raw = signer.sign_transaction(&web3, tx).await?;
+    let sent = match web3
+        .eth()
+        .send_raw_transaction(raw)
+        .await
+        .map_err(Error::from)
+    {
+        Ok(hash) => Sent::Replacement(hash),
+        // the original may have been mined in the meantime
+        Err(Error::NonceConflict(e)) => {
+            match web3.eth().transaction_receipt(original.hash).await? {
+                Some(receipt) => Sent::Refused(Box::new(TxOutcome::new(receipt, &known_abi()?))),
+                None => return Err(Error::NonceConflict(e)),
+            }
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(PendingReplacement {
+        web3,
+        original: original.hash,
+        sent,
+        wait: options.wait_policy(network),
+    })
+}
+
+/// Raises `fee`, the current estimate, to at least the replacement threshold over what
+/// `original` offered. The configured fee caps do not apply to the bump, since the node
+/// rejects anything below it anyway.
+fn bump(fee: Fee, original: &Transaction) -> Fee {
+    let is_eip1559 = original.transaction_type == Some(U64::from(2));
+    let original_max = if is_eip1559 {
+        original.max_fee_per_gas
+    } else {
+        original.gas_price
+    }
+    .unwrap_or_default();
+    let original_tip = if is_eip1559 {
+        original.max_priority_fee_per_gas
+    } else {
+        original.gas_price
+    }
+    .unwrap_or_default();
+
+    match fee {
+        Fee::Legacy { gas_price } => Fee::Legacy {
+            gas_price: gas_price.max(bumped(original_max)),
+        },
+        Fee::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        } => {
+            let tip = max_priority_fee_per_gas.max(bumped(original_tip));
+            Fee::Eip1559 {
+                max_fee_per_gas: max_fee_per_gas.max(bumped(original_max)).max(tip),
+                max_priority_fee_per_gas: tip,
+            }
+        }
+    }
+}
+
+/// `value` raised to [`REPLACEMENT_FEE_PERMILLE`], rounded up.
+fn bumped(value: U256) -> U256 {
+    (value * REPLACEMENT_FEE_PERMILLE + 999) / 1000
+}
+
+/// Polls until one of `hashes`, which share a nonce, is mined.
+async fn wait_for_either(
+    web3: &Web3<Http>,
+    hashes: [H256; 2],
+    poll_interval: time::Duration,
+) -> Web3Result<TransactionReceipt> {
+    loop {
+        for hash in hashes {
+            if let Some(receipt) = web3.eth().transaction_receipt(hash).await? {
+                if receipt.block_number.is_some() {
+                    return Ok(receipt);
+                }
+            }
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
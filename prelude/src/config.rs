@@ -0,0 +1,735 @@
+use crate::{Currency, FeeSpeed, Network, NetworkRegistry, Schema, GAS_MULTIPLIER};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const DEFAULT_PATH: &str = "config.toml";
+pub const DEFAULT_PROFILE: &str = "default";
+
+const NETWORK_FIELDS: [&str; 11] = [
+    "chain_url",
+    "chain_id",
+    "currency_symbol",
+    "currency_decimals",
+    "eip1559",
+    "nft_721_address",
+    "nft_1155_address",
+    "explorer_url",
+    "gas",
+    "rpc",
+    "wait",
+];
+const GAS_FIELDS: [&str; 6] = [
+    "limit",
+    "multiplier",
+    "price",
+    "speed",
+    "max_fee_cap",
+    "max_priority_fee_cap",
+];
+
+const RPC_FIELDS: [&str; 3] = ["timeout", "connect_timeout", "pool_max_idle"];
+
+const WAIT_FIELDS: [&str; 3] = ["confirmations", "poll_interval", "timeout"];
+
+/// Gas settings for a network. Fee amounts are in wei per unit of gas; `price` pins a
+/// legacy gas price instead of asking the node. Gas limits are estimated per transaction
+/// and scaled by `multiplier` unless `limit` fixes them.
+#[derive(PartialEq, Clone, Debug)]
+pub struct GasPolicy {
+    pub limit: Option<u64>,
+    pub multiplier: f64,
+    pub price: Option<u64>,
+    pub speed: FeeSpeed,
+    pub max_fee_cap: Option<u64>,
+    pub max_priority_fee_cap: Option<u64>,
+}
+
+impl Default for GasPolicy {
+    fn default() -> Self {
+        GasPolicy {
+            limit: None,
+            multiplier: GAS_MULTIPLIER,
+            price: None,
+            speed: FeeSpeed::default(),
+            max_fee_cap: None,
+            max_priority_fee_cap: None,
+        }
+    }
+}
+
+/// HTTP settings for the JSON-RPC connection. Timeouts are given in seconds in config;
+/// `pool_max_idle` bounds the idle keep-alive connections kept per host.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RpcPolicy {
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub pool_max_idle: usize,
+}
+
+impl Default for RpcPolicy {
+    fn default() -> Self {
+        RpcPolicy {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            pool_max_idle: 8,
+        }
+    }
+}
+
+/// How long writes wait for their receipt. The including block counts as the first
+/// confirmation; 0 confirmations returns as soon as the transaction is broadcast.
+/// `poll_interval` is given in (fractional) seconds and `timeout` in seconds in config.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct WaitPolicy {
+    pub confirmations: u64,
+    pub poll_interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for WaitPolicy {
+    fn default() -> Self {
+        WaitPolicy {
+            confirmations: 1,
+            poll_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct NetworkConfig {
+    network: Network,
+    nft_721_address: Option<String>,
+    nft_1155_address: Option<String>,
+    explorer_url: Option<String>,
+    gas: GasPolicy,
+    rpc: RpcPolicy,
+    wait: WaitPolicy,
+}
+
+impl NetworkConfig {
+    pub fn new(network: Network) -> Self {
+        NetworkConfig {
+            network,
+            nft_721_address: None,
+            nft_1155_address: None,
+            explorer_url: None,
+            gas: GasPolicy::default(),
+            rpc: RpcPolicy::default(),
+            wait: WaitPolicy::default(),
+        }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn chain_url(&self) -> &str {
+        &self.network.chain_url
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.network.chain_id
+    }
+
+    pub fn nft_721_address(&self) -> ConfigResult<String> {
+        self.nft_721_address
+            .clone()
+            .ok_or_else(|| Error::NotFound(format!("nft_721_address for {}", self.network)))
+    }
+
+    pub fn nft_1155_address(&self) -> ConfigResult<String> {
+        self.nft_1155_address
+            .clone()
+            .ok_or_else(|| Error::NotFound(format!("nft_1155_address for {}", self.network)))
+    }
+
+    pub(crate) fn set_contract_address(&mut self, schema: Schema, address: String) {
+        match schema {
+            Schema::ERC721 => self.nft_721_address = Some(address),
+            Schema::ERC1155 => self.nft_1155_address = Some(address),
+        }
+    }
+
+    pub fn explorer_url(&self) -> Option<&str> {
+        self.explorer_url.as_deref()
+    }
+
+    pub fn gas(&self) -> &GasPolicy {
+        &self.gas
+    }
+
+    pub fn rpc(&self) -> &RpcPolicy {
+        &self.rpc
+    }
+
+    pub fn wait(&self) -> &WaitPolicy {
+        &self.wait
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Profile {
+    name: String,
+    networks: BTreeMap<String, NetworkConfig>,
+}
+
+impl Profile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn network(&self, name: &str) -> ConfigResult<NetworkConfig> {
+        self.networks.get(name).cloned().ok_or_else(|| {
+            let names: Vec<&str> = self.networks.keys().map(|k| k.as_str()).collect();
+            Error::NotFound(format!(
+                "network {} in profile {} (configured: {})",
+                name,
+                self.name,
+                names.join(", ")
+            ))
+        })
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = &NetworkConfig> {
+        self.networks.values()
+    }
+
+    /// Adds a network at runtime, replacing any configured network of the same name.
+    pub fn register(&mut self, config: NetworkConfig) {
+        self.networks.insert(config.network.name.clone(), config);
+    }
+}
+
+/// Loads `profile` from a TOML or JSON file (chosen by extension) and validates
+/// every network in it, collecting all problems into a single `Error::Invalid`.
+pub fn load<P: AsRef<Path>>(path: P, profile: &str) -> ConfigResult<Profile> {
+    load_with(path, profile, &NetworkRegistry::default())
+}
+
+/// Like `load`, but networks named in `registry` only need to override what differs
+/// from the registered definition (typically just `chain_url`).
+pub fn load_with<P: AsRef<Path>>(
+    path: P,
+    profile: &str,
+    registry: &NetworkRegistry,
+) -> ConfigResult<Profile> {
+    let path = path.as_ref();
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+    let root = parse(path, &text)?;
+
+    let profiles = root
+        .get("profiles")
+        .and_then(Value::as_object)
+        .ok_or_else(|| Error::Parse(format!("{}: missing [profiles] table", path.display())))?;
+    let tables = profiles
+        .get(profile)
+        .and_then(Value::as_object)
+        .ok_or_else(|| Error::NotFound(format!("profile {}", profile)))?;
+
+    let mut validator = Validator::default();
+    let mut networks = BTreeMap::new();
+    for (name, value) in tables {
+        let at = format!("profiles.{}.{}", profile, name);
+        let table = match value.as_object() {
+            Some(table) => table,
+            None => {
+                validator.issue(&at, "must be a table");
+                continue;
+            }
+        };
+        if let Some(config) = validator.network(&at, name, registry.get(name), table) {
+            networks.insert(name.clone(), config);
+        }
+    }
+
+    if !validator.issues.is_empty() {
+        return Err(Error::Invalid(validator.issues));
+    }
+
+    Ok(Profile {
+        name: profile.to_string(),
+        networks,
+    })
+}
+
+fn parse(path: &Path, text: &str) -> ConfigResult<Value> {
+    let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(text).map_err(|e| e.to_string()),
+        _ => toml::from_str::<toml::Value>(text)
+            .map_err(|e| e.to_string())
+            .and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string())),
+    };
+    parsed.map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))
+}
+
+#[derive(Default)]
+struct Validator {
+    issues: Vec<String>,
+}
+
+impl Validator {
+    fn issue(&mut self, at: &str, msg: &str) {
+        self.issues.push(format!("{}: {}", at, msg));
+    }
+
+    fn network(
+        &mut self,
+        at: &str,
+        name: &str,
+        preset: Option<&Network>,
+        table: &Map<String, Value>,
+    ) -> Option<NetworkConfig> {
+        self.unknown_fields(at, table, &NETWORK_FIELDS);
+
+        let chain_url = self.url(at, table, "chain_url", true);
+        let chain_id = self
+            .u64(at, table, "chain_id", preset.is_none())
+            .or_else(|| preset.map(|p| p.chain_id));
+        let currency = self.currency(at, table, preset);
+        let eip1559 = self
+            .bool(at, table, "eip1559")
+            .or_else(|| preset.map(|p| p.eip1559))
+            .unwrap_or(true);
+        let nft_721_address = self.address(at, table, "nft_721_address");
+        let nft_1155_address = self.address(at, table, "nft_1155_address");
+        let explorer_url = self.url(at, table, "explorer_url", false);
+        let gas = self.gas(at, table);
+        let rpc = self.rpc(at, table);
+        let wait = self.wait(at, table);
+
+        if chain_id == Some(0) {
+            self.issue(&format!("{}.chain_id", at), "must be greater than 0");
+        }
+
+        Some(NetworkConfig {
+            network: Network {
+                name: name.to_string(),
+                chain_id: chain_id?,
+                chain_url: chain_url?,
+                currency: currency?,
+                eip1559,
+            },
+            nft_721_address,
+            nft_1155_address,
+            explorer_url,
+            gas: gas?,
+            rpc: rpc?,
+            wait: wait?,
+        })
+    }
+
+    fn currency(
+        &mut self,
+        at: &str,
+        table: &Map<String, Value>,
+        preset: Option<&Network>,
+    ) -> Option<Currency> {
+        let mut currency = preset.map(|p| p.currency.clone()).unwrap_or_default();
+        if let Some(symbol) = self.string(at, table, "currency_symbol", false) {
+            currency.symbol = symbol;
+        }
+        if let Some(decimals) = self.u64(at, table, "currency_decimals", false) {
+            if decimals > 36 {
+                self.issue(&format!("{}.currency_decimals", at), "must be at most 36");
+                return None;
+            }
+            currency.decimals = decimals as u8;
+        }
+        Some(currency)
+    }
+
+    fn gas(&mut self, at: &str, table: &Map<String, Value>) -> Option<GasPolicy> {
+        let mut policy = GasPolicy::default();
+        let value = match table.get("gas") {
+            Some(value) => value,
+            None => return Some(policy),
+        };
+        let at = format!("{}.gas", at);
+        let table = match value.as_object() {
+            Some(table) => table,
+            None => {
+                self.issue(&at, "must be a table");
+                return None;
+            }
+        };
+        self.unknown_fields(&at, table, &GAS_FIELDS);
+
+        policy.limit = self.u64(&at, table, "limit", false);
+        if let Some(multiplier) = self.f64(&at, table, "multiplier") {
+            if multiplier < 1.0 {
+                self.issue(&format!("{}.multiplier", at), "must be at least 1.0");
+            }
+            policy.multiplier = multiplier;
+        }
+        policy.price = self.u64(&at, table, "price", false);
+        policy.max_fee_cap = self.u64(&at, table, "max_fee_cap", false);
+        policy.max_priority_fee_cap = self.u64(&at, table, "max_priority_fee_cap", false);
+        if let Some(speed) = self.string(&at, table, "speed", false) {
+            match FeeSpeed::from_str(&speed) {
+                Ok(speed) => policy.speed = speed,
+                Err(_) => self.issue(&format!("{}.speed", at), "must be slow, standard or fast"),
+            }
+        }
+        if let (Some(max_fee), Some(max_priority_fee)) =
+            (policy.max_fee_cap, policy.max_priority_fee_cap)
+        {
+            if max_priority_fee > max_fee {
+                self.issue(&at, "max_priority_fee_cap must not exceed max_fee_cap");
+            }
+        }
+        Some(policy)
+    }
+
+    fn rpc(&mut self, at: &str, table: &Map<String, Value>) -> Option<RpcPolicy> {
+        let mut policy = RpcPolicy::default();
+        let value = match table.get("rpc") {
+            Some(value) => value,
+            None => return Some(policy),
+        };
+        let at = format!("{}.rpc", at);
+        let table = match value.as_object() {
+            Some(table) => table,
+            None => {
+                self.issue(&at, "must be a table");
+                return None;
+            }
+        };
+        self.unknown_fields(&at, table, &RPC_FIELDS);
+
+        for (key, timeout) in [
+            ("timeout", &mut policy.timeout),
+            ("connect_timeout", &mut policy.connect_timeout),
+        ] {
+            if let Some(secs) = self.u64(&at, table, key, false) {
+                if secs == 0 {
+                    self.issue(&format!("{}.{}", at, key), "must be greater than 0");
+                }
+                *timeout = Duration::from_secs(secs);
+            }
+        }
+        if let Some(max_idle) = self.u64(&at, table, "pool_max_idle", false) {
+            policy.pool_max_idle = max_idle as usize;
+        }
+        Some(policy)
+    }
+
+    fn wait(&mut self, at: &str, table: &Map<String, Value>) -> Option<WaitPolicy> {
+        let mut policy = WaitPolicy::default();
+        let value = match table.get("wait") {
+            Some(value) => value,
+            None => return Some(policy),
+        };
+        let at = format!("{}.wait", at);
+        let table = match value.as_object() {
+            Some(table) => table,
+            None => {
+                self.issue(&at, "must be a table");
+                return None;
+            }
+        };
+        self.unknown_fields(&at, table, &WAIT_FIELDS);
+
+        if let Some(confirmations) = self.u64(&at, table, "confirmations", false) {
+            policy.confirmations = confirmations;
+        }
+        if let Some(secs) = self.f64(&at, table, "poll_interval") {
+            match Duration::try_from_secs_f64(secs) {
+                Ok(interval) if !interval.is_zero() => policy.poll_interval = interval,
+                _ => self.issue(
+                    &format!("{}.poll_interval", at),
+                    "must be a finite number greater than 0",
+                ),
+            }
+        }
+        if let Some(secs) = self.u64(&at, table, "timeout", false) {
+            if secs == 0 {
+                self.issue(&format!("{}.timeout", at), "must be greater than 0");
+            }
+            policy.timeout = Duration::from_secs(secs);
+        }
+        Some(policy)
+    }
+
+    fn unknown_fields(&mut self, at: &str, table: &Map<String, Value>, known: &[&str]) {
+        for key in table.keys() {
+            if !known.contains(&key.as_str()) {
+                self.issue(&format!("{}.{}", at, key), "unknown field");
+            }
+        }
+    }
+
+    fn string(
+        &mut self,
+        at: &str,
+        table: &Map<String, Value>,
+        key: &str,
+        required: bool,
+    ) -> Option<String> {
+        let at = format!("{}.{}", at, key);
+        match table.get(key) {
+            Some(Value::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+            Some(Value::String(_)) | None => {
+                if required {
+                    self.issue(&at, "is required");
+                }
+                None
+            }
+            Some(_) => {
+                self.issue(&at, "must be a string");
+                None
+            }
+        }
+    }
+
+    fn u64(
+        &mut self,
+        at: &str,
+        table: &Map<String, Value>,
+        key: &str,
+        required: bool,
+    ) -> Option<u64> {
+        let at = format!("{}.{}", at, key);
+        match table.get(key) {
+            Some(value) => match value.as_u64() {
+                Some(v) => Some(v),
+                None => {
+                    self.issue(&at, "must be a non-negative integer");
+                    None
+                }
+            },
+            None => {
+                if required {
+                    self.issue(&at, "is required");
+                }
+                None
+            }
+        }
+    }
+
+    fn f64(&mut self, at: &str, table: &Map<String, Value>, key: &str) -> Option<f64> {
+        let value = table.get(key)?;
+        if value.as_f64().is_none() {
+            self.issue(&format!("{}.{}", at, key), "must be a number");
+        }
+        value.as_f64()
+    }
+
+    fn bool(&mut self, at: &str, table: &Map<String, Value>, key: &str) -> Option<bool> {
+        let value = table.get(key)?;
+        if value.as_bool().is_none() {
+            self.issue(&format!("{}.{}", at, key), "must be a boolean");
+        }
+        value.as_bool()
+    }
+
+    fn url(
+        &mut self,
+        at: &str,
+        table: &Map<String, Value>,
+        key: &str,
+        required: bool,
+    ) -> Option<String> {
+        let value = self.string(at, table, key, required)?;
+        let re = Regex::new(r"^https?://\S+$").unwrap();
+        if !re.is_match(&value) {
+            self.issue(&format!("{}.{}", at, key), "must be an http(s) url");
+            return None;
+        }
+        Some(value)
+    }
+
+    fn address(&mut self, at: &str, table: &Map<String, Value>, key: &str) -> Option<String> {
+        let value = self.string(at, table, key, false)?;
+        let re = Regex::new(r"^0x[0-9a-fA-F]{40}$").unwrap();
+        if !re.is_match(&value) {
+            self.issue(
+                &format!("{}.{}", at, key),
+                "must be a 0x-prefixed 20 byte address",
+            );
+            return None;
+        }
+        Some(value)
+    }
+}
+
+pub type ConfigResult<T> = Result<T, Error>;
+
+#[derive(thiserror::Error, Debug, PartialOrd, PartialEq, Clone)]
+pub enum Error {
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("invalid config: {}", .0.join(", "))]
+    Invalid(Vec<String>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Writes `text` to a file named `name` in the temp dir, unique to this test run.
+    fn write(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("config-{}-{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn reports_every_invalid_toml_field() {
+        let path = write(
+            "invalid.toml",
+            r#"
+[profiles.default.Geth]
+chain_url = "ftp://127.0.0.1"
+eip1559 = "yes"
+colour = "red"
+
+[profiles.default.Geth.gas]
+multiplier = 0.5
+
+[profiles.default.Polygon]
+nft_721_address = "0x123"
+"#,
+        );
+
+        assert_eq!(
+            load(&path, "default").unwrap_err(),
+            Error::Invalid(vec![
+                "profiles.default.Geth.colour: unknown field".to_string(),
+                "profiles.default.Geth.chain_url: must be an http(s) url".to_string(),
+                "profiles.default.Geth.chain_id: is required".to_string(),
+                "profiles.default.Geth.eip1559: must be a boolean".to_string(),
+                "profiles.default.Geth.gas.multiplier: must be at least 1.0".to_string(),
+                "profiles.default.Polygon.chain_url: is required".to_string(),
+                "profiles.default.Polygon.nft_721_address: must be a 0x-prefixed 20 byte address"
+                    .to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_every_invalid_json_field() {
+        let path = write(
+            "invalid.json",
+            r#"{
+  "profiles": {
+    "test": {
+      "Geth": {
+        "chain_url": "http://127.0.0.1:8545",
+        "chain_id": -1,
+        "currency_decimals": 40,
+        "gas": { "speed": "instant" },
+        "rpc": { "timeout": 0 },
+        "wait": { "poll_interval": 0, "timeout": "soon" }
+      },
+      "Local": "http://127.0.0.1:8545"
+    }
+  }
+}"#,
+        );
+
+        assert_eq!(
+            load(&path, "test").unwrap_err(),
+            Error::Invalid(vec![
+                "profiles.test.Geth.chain_id: must be a non-negative integer".to_string(),
+                "profiles.test.Geth.currency_decimals: must be at most 36".to_string(),
+                "profiles.test.Geth.gas.speed: must be slow, standard or fast".to_string(),
+                "profiles.test.Geth.rpc.timeout: must be greater than 0".to_string(),
+                "profiles.test.Geth.wait.poll_interval: must be a finite number greater than 0"
+                    .to_string(),
+                "profiles.test.Geth.wait.timeout: must be a non-negative integer".to_string(),
+                "profiles.test.Local: must be a table".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_poll_intervals_that_are_not_finite() {
+        let path = write(
+            "poll.toml",
+            r#"
+[profiles.default.Nan]
+chain_url = "http://127.0.0.1:8545"
+chain_id = 15
+wait = { poll_interval = nan }
+
+[profiles.default.Inf]
+chain_url = "http://127.0.0.1:8545"
+chain_id = 15
+wait = { poll_interval = inf }
+
+[profiles.default.Huge]
+chain_url = "http://127.0.0.1:8545"
+chain_id = 15
+wait = { poll_interval = 1e300 }
+
+[profiles.default.Negative]
+chain_url = "http://127.0.0.1:8545"
+chain_id = 15
+wait = { poll_interval = -1.0 }
+
+[profiles.default.Fast]
+chain_url = "http://127.0.0.1:8545"
+chain_id = 15
+wait = { poll_interval = 0.25 }
+"#,
+        );
+
+        let Err(Error::Invalid(mut issues)) = load(&path, "default") else {
+            panic!("poll intervals were accepted");
+        };
+        issues.sort();
+        assert_eq!(
+            issues,
+            vec![
+                "profiles.default.Huge.wait.poll_interval: must be a finite number greater than 0"
+                    .to_string(),
+                "profiles.default.Inf.wait.poll_interval: must be a number".to_string(),
+                "profiles.default.Nan.wait.poll_interval: must be a number".to_string(),
+                "profiles.default.Negative.wait.poll_interval: must be a finite number greater than 0"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn preset_network_needs_only_chain_url() {
+        let path = write(
+            "preset.toml",
+            r#"
+[profiles.default.Polygon]
+chain_url = "https://rpc-mumbai.example"
+"#,
+        );
+
+        let config = load(&path, "default").unwrap().network("Polygon").unwrap();
+        assert_eq!(config.chain_url(), "https://rpc-mumbai.example");
+        assert_eq!(config.chain_id(), 80001);
+        assert_eq!(config.network().currency, Currency::new("MATIC", 18));
+        assert!(config.network().eip1559);
+        assert_eq!(config.gas().multiplier, GAS_MULTIPLIER);
+        assert_eq!(config.wait().confirmations, 1);
+        assert!(config.nft_721_address().is_err());
+    }
+
+    #[test]
+    fn missing_profile_is_not_found() {
+        let path = write("profile.toml", "[profiles.default]\n");
+
+        assert_eq!(
+            load(&path, "staging").unwrap_err(),
+            Error::NotFound("profile staging".to_string())
+        );
+    }
+}
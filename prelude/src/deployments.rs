@@ -0,0 +1,275 @@
+use crate::config::{ConfigResult, Error, NetworkConfig};
+use crate::Schema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_PATH: &str = "deployments.json";
+
+/// A contract deployed by the CLI.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Deployment {
+    pub address: String,
+    pub chain_id: u64,
+    pub tx_hash: String,
+    pub block_number: Option<u64>,
+    pub deployer: String,
+    /// keccak256 of the creation bytecode, identifying the contract version.
+    pub bytecode_hash: String,
+    /// Unix time in seconds.
+    pub deployed_at: u64,
+}
+
+impl Deployment {
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+struct Contracts {
+    /// Address of the deployment the clients use.
+    active: Option<String>,
+    deployments: Vec<Deployment>,
+}
+
+/// Every contract deployed per network and schema, kept in a JSON file. The active
+/// deployment of each takes precedence over the address in config.
+#[derive(Clone, Debug)]
+pub struct Deployments {
+    path: PathBuf,
+    networks: BTreeMap<String, BTreeMap<String, Contracts>>,
+}
+
+impl Deployments {
+    /// Reads the registry at `path`; a missing file is an empty registry.
+    pub fn load<P: AsRef<Path>>(path: P) -> ConfigResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let networks = if path.exists() {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+            serde_json::from_str(&text)
+                .map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Deployments { path, networks })
+    }
+
+    pub fn save(&self) -> ConfigResult<()> {
+        let json = serde_json::to_string_pretty(&self.networks)
+            .map_err(|e| Error::Parse(format!("{}: {}", self.path.display(), e)))?;
+        fs::write(&self.path, json)
+            .map_err(|e| Error::Io(format!("{}: {}", self.path.display(), e)))
+    }
+
+    /// Adds `deployment` and makes it the active one.
+    pub fn record(&mut self, network: &str, schema: Schema, deployment: Deployment) {
+        let contracts = self
+            .networks
+            .entry(network.to_string())
+            .or_default()
+            .entry(schema.to_string())
+            .or_default();
+        contracts.active = Some(deployment.address.clone());
+        contracts.deployments.push(deployment);
+    }
+
+    /// Makes the recorded deployment at `address` the active one.
+    pub fn activate(&mut self, network: &str, schema: Schema, address: &str) -> ConfigResult<()> {
+        let contracts = self
+            .networks
+            .get_mut(network)
+            .and_then(|n| n.get_mut(&schema.to_string()));
+        let recorded = contracts.as_ref().and_then(|c| {
+            c.deployments
+                .iter()
+                .find(|d| same_address(&d.address, address))
+                .map(|d| d.address.clone())
+        });
+        match (contracts, recorded) {
+            (Some(contracts), Some(recorded)) => {
+                contracts.active = Some(recorded);
+                Ok(())
+            }
+            _ => Err(Error::NotFound(format!(
+                "{} deployment {} on {}",
+                schema, address, network
+            ))),
+        }
+    }
+
+    pub fn active(&self, network: &str, schema: Schema) -> Option<&Deployment> {
+        let contracts = self.networks.get(network)?.get(&schema.to_string())?;
+        let active = contracts.active.as_deref()?;
+        contracts
+            .deployments
+            .iter()
+            .rev()
+            .find(|d| same_address(&d.address, active))
+    }
+
+    /// Deployments on `network` of `schema`, oldest first.
+    pub fn list(&self, network: &str, schema: Schema) -> &[Deployment] {
+        self.networks
+            .get(network)
+            .and_then(|n| n.get(&schema.to_string()))
+            .map(|c| c.deployments.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Points `config` at the active deployments on its network, if they were made on
+    /// the same chain.
+    pub fn apply(&self, config: &mut NetworkConfig) {
+        for schema in [Schema::ERC721, Schema::ERC1155] {
+            if let Some(deployment) = self.active(&config.network().name, schema) {
+                if deployment.chain_id == config.chain_id() {
+                    config.set_contract_address(schema, deployment.address.clone());
+                }
+            }
+        }
+    }
+}
+
+fn same_address(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    const FIRST: &str = "0x1111111111111111111111111111111111111111";
+    const SECOND: &str = "0xAbCdEf0000000000000000000000000000000002";
+
+    /// A path in the temp dir, unique to this test run.
+    fn temp(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("deployments-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn deployment(address: &str, chain_id: u64) -> Deployment {
+        Deployment {
+            address: address.to_string(),
+            chain_id,
+            tx_hash: format!("0x{}", "aa".repeat(32)),
+            block_number: Some(100),
+            deployer: format!("0x{}", "22".repeat(20)),
+            bytecode_hash: format!("0x{}", "bb".repeat(32)),
+            deployed_at: 1_700_000_000,
+        }
+    }
+
+    fn network(chain_id: u64) -> NetworkConfig {
+        let path = temp(&format!("config-{}.toml", chain_id));
+        fs::write(
+            &path,
+            format!(
+                r#"
+[profiles.default.Geth]
+chain_url = "http://127.0.0.1:8545"
+chain_id = {}
+nft_721_address = "0x3333333333333333333333333333333333333333"
+"#,
+                chain_id
+            ),
+        )
+        .unwrap();
+        config::load(&path, "default")
+            .unwrap()
+            .network("Geth")
+            .unwrap()
+    }
+
+    #[test]
+    fn record_makes_the_new_deployment_active() {
+        let path = temp("record.json");
+        let mut registry = Deployments::load(&path).unwrap();
+        registry.record("Geth", Schema::ERC721, deployment(FIRST, 15));
+        registry.record("Geth", Schema::ERC721, deployment(SECOND, 15));
+        registry.save().unwrap();
+
+        let registry = Deployments::load(&path).unwrap();
+        assert_eq!(registry.list("Geth", Schema::ERC721).len(), 2);
+        assert_eq!(
+            registry.active("Geth", Schema::ERC721),
+            Some(&deployment(SECOND, 15))
+        );
+        assert_eq!(registry.active("Geth", Schema::ERC1155), None);
+        assert_eq!(registry.active("Polygon", Schema::ERC721), None);
+    }
+
+    #[test]
+    fn activate_matches_addresses_case_insensitively() {
+        let mut registry = Deployments::load(temp("activate.json")).unwrap();
+        registry.record("Geth", Schema::ERC721, deployment(FIRST, 15));
+        registry.record("Geth", Schema::ERC721, deployment(SECOND, 15));
+
+        registry.activate("Geth", Schema::ERC721, FIRST).unwrap();
+        assert_eq!(
+            registry.active("Geth", Schema::ERC721),
+            Some(&deployment(FIRST, 15))
+        );
+
+        for address in [
+            SECOND.to_lowercase(),
+            format!("0x{}", SECOND[2..].to_uppercase()),
+        ] {
+            registry.activate("Geth", Schema::ERC721, &address).unwrap();
+            assert_eq!(
+                registry.active("Geth", Schema::ERC721),
+                Some(&deployment(SECOND, 15))
+            );
+        }
+    }
+
+    #[test]
+    fn activate_rejects_unknown_addresses() {
+        let mut registry = Deployments::load(temp("unknown.json")).unwrap();
+        registry.record("Geth", Schema::ERC721, deployment(FIRST, 15));
+
+        for (network, schema, address) in [
+            ("Geth", Schema::ERC721, SECOND),
+            ("Geth", Schema::ERC1155, FIRST),
+            ("Polygon", Schema::ERC721, FIRST),
+        ] {
+            assert!(matches!(
+                registry.activate(network, schema, address),
+                Err(Error::NotFound(_))
+            ));
+        }
+        assert_eq!(
+            registry.active("Geth", Schema::ERC721),
+            Some(&deployment(FIRST, 15))
+        );
+    }
+
+    #[test]
+    fn apply_skips_deployments_on_another_chain() {
+        let mut registry = Deployments::load(temp("apply.json")).unwrap();
+        registry.record("Geth", Schema::ERC721, deployment(FIRST, 15));
+        registry.record("Geth", Schema::ERC1155, deployment(SECOND, 15));
+
+        let mut same_chain = network(15);
+        registry.apply(&mut same_chain);
+        assert_eq!(same_chain.nft_721_address(), Ok(FIRST.to_string()));
+        assert_eq!(same_chain.nft_1155_address(), Ok(SECOND.to_string()));
+
+        let mut other_chain = network(16);
+        registry.apply(&mut other_chain);
+        assert_eq!(
+            other_chain.nft_721_address(),
+            Ok("0x3333333333333333333333333333333333333333".to_string())
+        );
+        assert!(other_chain.nft_1155_address().is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Currency {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl Currency {
+    pub fn new(symbol: &str, decimals: u8) -> Self {
+        Currency {
+            symbol: symbol.to_string(),
+            decimals,
+        }
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::new("ETH", 18)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Network {
+    pub name: String,
+    pub chain_id: u64,
+    pub chain_url: String,
+    pub currency: Currency,
+    pub eip1559: bool,
+}
+
+impl Network {
+    pub fn new(name: &str, chain_id: u64, chain_url: &str) -> Self {
+        Network {
+            name: name.to_string(),
+            chain_id,
+            chain_url: chain_url.to_string(),
+            currency: Currency::default(),
+            eip1559: true,
+        }
+    }
+
+    /// Built-in networks. They carry no `chain_url`; that always comes from config.
+    pub fn presets() -> Vec<Network> {
+        vec![
+            Network::new("Ethereum", 5, ""),
+            Network {
+                currency: Currency::new("MATIC", 18),
+                ..Network::new("Polygon", 80001, "")
+            },
+            Network {
+                currency: Currency::new("AVAX", 18),
+                ..Network::new("Avalanche", 43113, "")
+            },
+        ]
+    }
+
+    pub fn preset(name: &str) -> Option<Network> {
+        Network::presets().into_iter().find(|n| n.name == name)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NetworkRegistry {
+    networks: BTreeMap<String, Network>,
+}
+
+impl NetworkRegistry {
+    pub fn empty() -> Self {
+        NetworkRegistry {
+            networks: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `network`, replacing any network already registered under the same name.
+    pub fn register(&mut self, network: Network) {
+        self.networks.insert(network.name.clone(), network);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Network> {
+        self.networks.get(name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.networks.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+impl Default for NetworkRegistry {
+    fn default() -> Self {
+        let mut registry = NetworkRegistry::empty();
+        for network in Network::presets() {
+            registry.register(network);
+        }
+        registry
+    }
+}
@@ -8,7 +8,7 @@ fn convert<'a>(value: &str, unit: &'a str) -> HashMap<&'a str, String> {
     let v = to_ether(value, unit);
     let mut map: HashMap<&'a str, String> = HashMap::new();
 
-    map.insert(unit, BigDecimal::from_str(&value).unwrap().to_string());
+    map.insert(unit, BigDecimal::from_str(value).unwrap().to_string());
 
     if unit != "wei" {
         map.insert("wei", s(&v, "1000000000000000000"));
@@ -17,30 +17,49 @@ fn convert<'a>(value: &str, unit: &'a str) -> HashMap<&'a str, String> {
         map.insert("ether", s(&v, "1"));
     }
 
-    return map;
+    map
 }
 
 fn m(v: &BigDecimal, u: &str) -> BigDecimal {
-    return v.mul(&BigDecimal::from_str(u).unwrap());
+    v.mul(&BigDecimal::from_str(u).unwrap())
 }
 
 fn s(v: &BigDecimal, u: &str) -> String {
-    return t(v.mul(&BigDecimal::from_str(u).unwrap()).to_string());
+    t(v.mul(&BigDecimal::from_str(u).unwrap()).to_string())
 }
 
 fn t(v: String) -> String {
     let re = Regex::new(r"(.*)\.0+$").unwrap();
     let v = re.replace_all(&v, "$1").to_string();
     let re = Regex::new(r"(.*\.\d+[1-9]+)(0+)$").unwrap();
-    return re.replace_all(&v, "$1").to_string();
+    re.replace_all(&v, "$1").to_string()
 }
 
 pub fn to_wei(value: &str, unit: &str) -> String {
-    return convert(&value, &unit).get("wei").unwrap().to_string();
+    convert(value, unit).get("wei").unwrap().to_string()
+}
+
+/// Converts an amount in the smallest unit into a native currency with `decimals` places.
+pub fn to_native(value: &str, decimals: u8) -> BigDecimal {
+    let v = BigDecimal::from_str(value).unwrap();
+    m(&v, &format!("1e-{}", decimals))
+}
+
+/// Converts an amount of a native currency with `decimals` places into the smallest unit.
+/// `None` when `value` is not a number or is more precise than the currency.
+pub fn from_native(value: &str, decimals: u8) -> Option<String> {
+    let v = m(
+        &BigDecimal::from_str(value).ok()?,
+        &format!("1e{}", decimals),
+    );
+    if !v.is_integer() {
+        return None;
+    }
+    Some(v.with_scale(0).to_string())
 }
 
 pub fn to_ether(value: &str, unit: &str) -> BigDecimal {
-    let v = BigDecimal::from_str(&value).unwrap();
+    let v = BigDecimal::from_str(value).unwrap();
 
     if unit == "wei" {
         return m(&v, "0.000000000000000001");
@@ -51,3 +70,20 @@ pub fn to_ether(value: &str, unit: &str) -> BigDecimal {
 
     panic!("unit not supported");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_native_scales_by_the_currency_decimals() {
+        assert_eq!(
+            from_native("0.1", 18),
+            Some("100000000000000000".to_string())
+        );
+        assert_eq!(from_native("0.1", 6), Some("100000".to_string()));
+        assert_eq!(from_native("2", 0), Some("2".to_string()));
+        assert_eq!(from_native("0.0000001", 6), None);
+        assert_eq!(from_native("abc", 18), None);
+    }
+}